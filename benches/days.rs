@@ -0,0 +1,118 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use aoc_2021_rust::*;
+
+/// Benchmark the given solvers of a day against each `(label, input)` pair.
+macro_rules! bench_day {
+    ($c:expr, $day:ident, $samples:expr, $inputs:expr, [$($name:literal => $solver:path),+ $(,)?]) => {{
+        let mut group = $c.benchmark_group(stringify!($day));
+        group.sample_size($samples);
+        for (label, input) in $inputs {
+            let data = $day::generator(input.trim());
+            $( group.bench_with_input(BenchmarkId::new($name, label), &data, |b, d| b.iter(|| $solver(black_box(d)))); )+
+        }
+        group.finish();
+    }};
+}
+
+fn sample(input: &str) -> Vec<(&'static str, String)> {
+    vec![("sample", input.to_string())]
+}
+
+fn both(input: &str, synthetic: String) -> Vec<(&'static str, String)> {
+    vec![("sample", input.to_string()), ("synthetic", synthetic)]
+}
+
+fn days(c: &mut Criterion) {
+    bench_day!(c, day1, 100, both(include_str!("input/day1.txt"), synth::day1(2000)), [
+        "part1" => day1::part1, "part1_golf1" => day1::part1_golf1,
+        "part2" => day1::part2, "part2_golf1" => day1::part2_golf1, "part2_window" => day1::part2_window,
+    ]);
+    bench_day!(c, day2, 100, both(include_str!("input/day2.txt"), synth::day2(1000)), [
+        "part1" => day2::part1, "part2" => day2::part2,
+    ]);
+    bench_day!(c, day3, 100, both(include_str!("input/day3.txt"), synth::day3(1000)), [
+        "part1" => day3::part1, "part2" => day3::part2, "part2_trie" => day3::part2_trie,
+    ]);
+    bench_day!(c, day4, 100, both(include_str!("input/day4.txt"), synth::day4(100)), [
+        "part1" => day4::part1, "part2" => day4::part2,
+    ]);
+    bench_day!(c, day5, 50, both(include_str!("input/day5.txt"), synth::day5(500, 1000)), [
+        "part1" => day5::part1, "part1_iter" => day5::part1_iter, "part1_iter2" => day5::part1_iter2,
+        "part2" => day5::part2, "part2_iter" => day5::part2_iter, "part2_bresenham" => day5::part2_bresenham,
+        "part1_sweep" => day5::part1_sweep, "part2_sweep" => day5::part2_sweep,
+    ]);
+    bench_day!(c, day6, 100, both(include_str!("input/day6.txt"), synth::day6(300)), [
+        "part1_rotate" => day6::part1_rotate, "part2_rotate" => day6::part2_rotate,
+        "part1" => day6::part1, "part2" => day6::part2, "part2_big" => day6::part2_big,
+        "part1_matrix" => day6::part1_matrix, "part2_matrix" => day6::part2_matrix,
+        "part1_model" => day6::part1_model, "part2_model" => day6::part2_model,
+    ]);
+    bench_day!(c, day7, 100, both(include_str!("input/day7.txt"), synth::day7(1000)), [
+        "part1" => day7::part1, "part2" => day7::part2,
+    ]);
+    bench_day!(c, day8, 100, both(include_str!("input/day8.txt"), synth::day8(200)), [
+        "part1" => day8::part1, "part2" => day8::part2,
+        "part2_propagate" => day8::part2_propagate, "part2_permutations" => day8::part2_permutations,
+    ]);
+    bench_day!(c, day9, 50, both(include_str!("input/day9.txt"), synth::digit_grid(100, 0, 9)), [
+        "part1" => day9::part1, "part2" => day9::part2,
+    ]);
+    bench_day!(c, day10, 100, both(include_str!("input/day10.txt"), synth::day10(100)), [
+        "part1" => day10::part1, "part2" => day10::part2,
+    ]);
+    bench_day!(c, day11, 50, both(include_str!("input/day11.txt"), synth::digit_grid(10, 0, 9)), [
+        "part1" => day11::part1, "part2" => day11::part2,
+    ]);
+    // random cave systems explode in the number of paths, sample only
+    bench_day!(c, day12, 100, sample(include_str!("input/day12.txt")), [
+        "part1" => day12::part1, "part2" => day12::part2,
+    ]);
+    bench_day!(c, day13, 100, both(include_str!("input/day13.txt"), synth::day13(1000, 10)), [
+        "part1" => day13::part1, "part2" => day13::part2,
+    ]);
+    bench_day!(c, day14, 100, both(include_str!("input/day14.txt"), synth::day14(20)), [
+        "part1" => day14::part1, "part2" => day14::part2, "part2_big" => day14::part2_big,
+    ]);
+    bench_day!(c, day15, 20, both(include_str!("input/day15.txt"), synth::digit_grid(100, 1, 9)), [
+        "part1" => day15::part1, "part2" => day15::part2,
+    ]);
+    bench_day!(c, day16, 100, both(include_str!("input/day16.txt"), synth::day16(5)), [
+        "part1" => day16::part1, "part2" => day16::part2,
+    ]);
+    bench_day!(c, day17, 20, both(include_str!("input/day17.txt"), synth::day17(10)), [
+        "part1" => day17::part1, "part2" => day17::part2,
+    ]);
+    bench_day!(c, day18, 20, both(include_str!("input/day18.txt"), synth::day18(100)), [
+        "part1" => day18::part1, "part2" => day18::part2,
+    ]);
+    // day19 is not benchmarked, its solver is unfinished
+    bench_day!(c, day20, 10, both(include_str!("input/day20.txt"), synth::day20(50)), [
+        "part1" => day20::part1, "part2" => day20::part2,
+    ]);
+    // the generator ignores its input, so there is nothing to scale
+    bench_day!(c, day21, 20, sample(include_str!("input/day21.txt")), [
+        "part1" => day21::part1,
+        "part2_cache" => day21::part2_cache, "part2_cache_naive" => day21::part2_cache_naive,
+        "part2_big" => day21::part2_big,
+    ]);
+    // part2 is unfinished and returns a placeholder
+    bench_day!(c, day22, 10, both(include_str!("input/day22.txt"), synth::day22(20, 20)), [
+        "part1" => day22::part1,
+    ]);
+    bench_day!(c, day23, 10, sample(include_str!("input/day23.txt")), [
+        "part1" => day23::part1, "part2" => day23::part2,
+    ]);
+}
+
+fn config() -> Criterion {
+    // compared against a saved baseline, changes count as regressions when they are
+    // significant at the 1% level and larger than 3% noise
+    Criterion::default().significance_level(0.01).noise_threshold(0.03)
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = days
+}
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+extern crate aoc_2021_rust;
+
+use std::{fs, process};
+use aoc_2021_rust::count::{Overflow, Width};
+use aoc_2021_rust::{day14, day21, day6};
+
+const USAGE: &str = "usage: count <day6|day14|day21> <n> <u64|u128|big> <input file>";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2);
+}
+
+fn arg<T: std::str::FromStr>(args: &[String], i: usize) -> T {
+    args.get(i).and_then(|a| a.parse().ok()).unwrap_or_else(|| fail(USAGE))
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.len() != 4 {
+        fail(USAGE);
+    }
+    let width = args[2].parse::<Width>().unwrap_or_else(|e| fail(&e));
+    let input = fs::read_to_string(&args[3]).unwrap_or_else(|e| fail(&format!("{}: {}", args[3], e)));
+    let input = input.trim();
+
+    // n is the number of days, steps or the winning score
+    let result: Result<String, Overflow> = match args[0].as_str() {
+        "day6" => day6::run_width(&day6::generator(input), arg(&args, 1), width),
+        "day14" => day14::step_n_width(&day14::generator(input), arg(&args, 1), width),
+        "day21" => {
+            let pos = input.lines().map(|l| l.rsplit(' ').next().and_then(|p| p.parse().ok()).unwrap_or_else(|| fail(&format!("invalid line {}", l)))).collect::<Vec<u8>>();
+            if pos.len() != 2 {
+                fail("expected two starting positions");
+            }
+            let game = day21::Game::new(10, arg(&args, 1)).unwrap_or_else(|e| fail(&e.to_string()));
+            game.wins_width(pos[0], pos[1], width)
+        }
+        _ => fail(USAGE),
+    };
+
+    match result {
+        Ok(count) => println!("{}", count),
+        Err(e) => fail(&e.to_string()),
+    }
+}
@@ -0,0 +1,34 @@
+extern crate aoc_2021_rust;
+
+use std::{env, fs};
+use aoc_2021_rust::detect::detect;
+
+fn main() {
+    let paths = env::args().skip(1).collect::<Vec<_>>();
+    if paths.is_empty() {
+        eprintln!("usage: detect <input file>...");
+        std::process::exit(2);
+    }
+
+    for path in paths {
+        let input = match fs::read_to_string(&path) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                continue;
+            }
+        };
+        let detection = detect(&input);
+
+        println!("{}", path);
+        if detection.candidates.is_empty() {
+            println!("  no matching day");
+        }
+        for c in &detection.candidates {
+            println!("  day{:<2} {:5.1}%", c.day, c.confidence * 100.0);
+        }
+        for r in &detection.rejected {
+            println!("  day{:<2} rejected: {}", r.day, r.reason);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+extern crate aoc_2021_rust;
+
+use std::env;
+use aoc_2021_rust::scaling::{scenarios, to_csv};
+
+fn main() {
+    // optional filter on the solver name, e.g. `day5`
+    let filter = env::args().nth(1).unwrap_or_default();
+    let series = scenarios()
+        .iter()
+        .filter(|s| s.solver.contains(&filter))
+        .map(|s| {
+            eprintln!("measuring {}", s.solver);
+            s.measure()
+        })
+        .collect::<Vec<_>>();
+    print!("{}", to_csv(&series));
+}
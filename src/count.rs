@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+/// Returned by the checked solvers once a count no longer fits into its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "count overflowed, use a wider count type")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+/// Unsigned counter type for the exponentially growing puzzles.
+pub trait Count: Clone + Ord + Display + Debug {
+    fn from_u64(n: u64) -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn zero() -> Self {
+        Self::from_u64(0)
+    }
+
+    fn try_add(&self, other: &Self) -> Result<Self, Overflow> {
+        self.checked_add(other).ok_or(Overflow)
+    }
+
+    fn try_sub(&self, other: &Self) -> Result<Self, Overflow> {
+        self.checked_sub(other).ok_or(Overflow)
+    }
+
+    fn try_mul(&self, other: &Self) -> Result<Self, Overflow> {
+        self.checked_mul(other).ok_or(Overflow)
+    }
+}
+
+macro_rules! impl_count {
+    ($($t:ty),*) => {$(
+        impl Count for $t {
+            fn from_u64(n: u64) -> Self {
+                n as $t
+            }
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+            fn checked_sub(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *other)
+            }
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+        }
+    )*}
+}
+
+impl_count!(u64, u128);
+
+/// Arbitrary precision unsigned integer, little endian base 2^32 limbs without trailing zeros.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn normalize(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    /// Divide in place by a small divisor, returning the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut rem = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = (rem << 32) | *limb as u64;
+            *limb = (cur / divisor as u64) as u32;
+            rem = cur % divisor as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u32
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        BigUint { limbs: vec![n as u32, (n >> 32) as u32] }.normalize()
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        BigUint { limbs: (0..4).map(|i| (n >> (32 * i)) as u32).collect() }.normalize()
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.limbs.is_empty() {
+            return write!(f, "0");
+        }
+        // peel off chunks of 9 decimal digits
+        let mut n = self.clone();
+        let mut chunks = Vec::new();
+        while !n.limbs.is_empty() {
+            chunks.push(n.div_rem_small(1_000_000_000));
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        chunks.iter().rev().try_for_each(|c| write!(f, "{:09}", c))
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ten = BigUint::from(10u64);
+        s.chars().try_fold(BigUint::zero(), |acc, c| {
+            let digit = c.to_string().parse::<u64>()?;
+            Ok(acc.checked_mul(&ten).unwrap().checked_add(&BigUint::from(digit)).unwrap())
+        })
+    }
+}
+
+impl Count for BigUint {
+    fn from_u64(n: u64) -> Self {
+        BigUint::from(n)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64 + *other.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        Some(BigUint { limbs }.normalize())
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if self < other {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+        for (i, &l) in self.limbs.iter().enumerate() {
+            let mut diff = l as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = if diff < 0 { diff += 1 << 32; 1 } else { 0 };
+            limbs.push(diff as u32);
+        }
+        Some(BigUint { limbs }.normalize())
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let cur = limbs[i + j] as u64 + a as u64 * b as u64 + carry;
+                limbs[i + j] = cur as u32;
+                carry = cur >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        Some(BigUint { limbs }.normalize())
+    }
+}
+
+/// Count type to run a checked solver with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Width {
+    U64,
+    U128,
+    Big,
+}
+
+impl FromStr for Width {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u64" => Ok(Width::U64),
+            "u128" => Ok(Width::U128),
+            "big" => Ok(Width::Big),
+            _ => Err(format!("unknown count width {}, expected u64, u128 or big", s)),
+        }
+    }
+}
+
+/// A checked solver that works with any count type, so [`Width::solve`] can pick one at runtime.
+pub trait Solver {
+    fn solve<C: Count>(&self) -> Result<C, Overflow>;
+}
+
+impl Width {
+    /// Run the solver with this count type.
+    pub fn solve(self, solver: &impl Solver) -> Result<String, Overflow> {
+        Ok(match self {
+            Width::U64 => solver.solve::<u64>()?.to_string(),
+            Width::U128 => solver.solve::<u128>()?.to_string(),
+            Width::Big => solver.solve::<BigUint>()?.to_string(),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_big() {
+        let a = BigUint::from(u64::MAX);
+        let b = BigUint::from(u128::MAX);
+        assert_eq!(a.checked_mul(&a).unwrap(), BigUint::from(u64::MAX as u128 * u64::MAX as u128));
+        assert_eq!(a.checked_add(&a).unwrap(), BigUint::from(u64::MAX as u128 * 2));
+        assert_eq!(b.checked_sub(&a).unwrap(), BigUint::from(u128::MAX - u64::MAX as u128));
+        assert_eq!(a.checked_sub(&b), None);
+        assert_eq!(b.to_string(), u128::MAX.to_string());
+        assert_eq!(BigUint::zero().to_string(), "0");
+
+        let big = "340282366920938463463374607431768211456000000001".parse::<BigUint>().unwrap();
+        assert_eq!(big.to_string(), "340282366920938463463374607431768211456000000001");
+        assert!(big > b);
+    }
+
+    #[test]
+    pub fn test_checked() {
+        assert_eq!(u64::MAX.try_add(&1), Err(Overflow));
+        assert_eq!((u64::MAX as u128).try_add(&1), Ok(u64::MAX as u128 + 1));
+        assert_eq!("u128".parse::<Width>(), Ok(Width::U128));
+        assert!("i32".parse::<Width>().is_err());
+    }
+}
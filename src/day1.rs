@@ -1,10 +1,17 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::num::ParseIntError;
 use itermore::IterMore;
 
 type Data = Vec<i32>;
 
+pub fn parse(input: &str) -> Result<Data, ParseIntError> {
+    input.lines().map(|l| l.parse::<i32>()).collect()
+}
+
 #[aoc_generator(day1)]
 pub fn generator(input: &str) -> Data {
-    input.lines().map(|l| l.parse::<i32>().unwrap()).collect()
+    parse(input).unwrap()
 }
 
 #[aoc(day1, part1)]
@@ -28,21 +35,361 @@ pub fn part2_golf1(inputs: &Data) -> usize {
     inputs.iter().windows().filter(|[x, _, _, y]| y > x).count()
 }
 
+#[aoc(day1, part2, window)]
+pub fn part2_window(inputs: &Data) -> usize {
+    depth_increases(inputs, 3)
+}
+
+/// Number of times the sum of a sliding window increases.
+/// Consecutive windows share all but one reading, so only the readings `window` apart are compared.
+pub fn depth_increases(inputs: &[i32], window: usize) -> usize {
+    if window == 0 {
+        return 0;
+    }
+    inputs.iter().zip(inputs[window.min(inputs.len())..].iter()).filter(|(x, y)| y > x).count()
+}
+
+/// Sums of all sliding windows, each computed from the previous one.
+pub fn window_sums(inputs: &[i32], window: usize) -> Vec<i32> {
+    if window == 0 || window > inputs.len() {
+        return Vec::new();
+    }
+    let mut sum: i32 = inputs[..window].iter().sum();
+    let mut sums = vec![sum];
+    for i in window..inputs.len() {
+        sum += inputs[i] - inputs[i - window];
+        sums.push(sum);
+    }
+    sums
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SonarReport {
+    pub window: usize,
+    pub increases: usize,
+    /// index of every window whose sum is larger than the one before
+    pub positions: Vec<usize>,
+    /// most increases in a row
+    pub longest_run: usize,
+    /// index and size of the largest decrease between two windows
+    pub largest_drop: Option<(usize, i32)>,
+}
+
+pub fn analyze(inputs: &[i32], window: usize) -> SonarReport {
+    let sums = window_sums(inputs, window);
+    let mut report = SonarReport { window, increases: 0, positions: Vec::new(), longest_run: 0, largest_drop: None };
+    let mut run = 0;
+
+    for (i, w) in sums.windows(2).enumerate() {
+        let change = w[1] - w[0];
+        if change > 0 {
+            report.positions.push(i + 1);
+            run += 1;
+            report.longest_run = report.longest_run.max(run);
+        } else {
+            run = 0;
+        }
+        if change < 0 && report.largest_drop.is_none_or(|(_, d)| -change > d) {
+            report.largest_drop = Some((i + 1, -change));
+        }
+    }
+    report.increases = report.positions.len();
+    report
+}
+
+/// Incremental version of [`analyze`] for endless readings, keeping only the last `window + 1` of them.
+#[derive(Debug, Clone)]
+pub struct SonarStream {
+    window: usize,
+    readings: VecDeque<i32>,
+    sum: i32,
+    seen: usize,
+    increases: usize,
+    run: usize,
+    longest_run: usize,
+    largest_drop: Option<(usize, i32)>,
+}
+
+impl SonarStream {
+    pub fn new(window: usize) -> Self {
+        SonarStream {
+            window,
+            readings: VecDeque::with_capacity(window + 1),
+            sum: 0,
+            seen: 0,
+            increases: 0,
+            run: 0,
+            longest_run: 0,
+            largest_drop: None,
+        }
+    }
+
+    pub fn push(&mut self, depth: i32) {
+        self.seen += 1;
+        if self.window == 0 {
+            return;
+        }
+        self.readings.push_back(depth);
+        self.sum += depth;
+        if self.readings.len() > self.window + 1 {
+            self.readings.pop_front();
+        }
+        if self.readings.len() <= self.window {
+            return;
+        }
+
+        // the newest window differs from the previous one by the oldest and newest reading
+        let oldest = self.readings[0];
+        self.sum -= oldest;
+        let change = depth - oldest;
+        if change > 0 {
+            self.increases += 1;
+            self.run += 1;
+            self.longest_run = self.longest_run.max(self.run);
+        } else {
+            self.run = 0;
+        }
+        if change < 0 && self.largest_drop.is_none_or(|(_, d)| -change > d) {
+            self.largest_drop = Some((self.seen - self.window, -change));
+        }
+    }
+
+    /// Push every line of the reader, failing on the first line that is not a depth.
+    pub fn read<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+        for line in reader.lines() {
+            let depth = line?.trim().parse::<i32>().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.push(depth);
+        }
+        Ok(())
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Number of readings pushed so far.
+    pub fn readings(&self) -> usize {
+        self.seen
+    }
+
+    /// Sum of the latest full window.
+    pub fn window_sum(&self) -> Option<i32> {
+        (self.window > 0 && self.seen >= self.window).then_some(self.sum)
+    }
+
+    pub fn increases(&self) -> usize {
+        self.increases
+    }
+
+    pub fn longest_run(&self) -> usize {
+        self.longest_run
+    }
+
+    pub fn largest_drop(&self) -> Option<(usize, i32)> {
+        self.largest_drop
+    }
+}
+
+impl Extend<i32> for SonarStream {
+    fn extend<T: IntoIterator<Item = i32>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|d| self.push(d));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// missing or non numeric line, severity is the length of the gap
+    Dropout,
+    /// reading far off the rolling median on both sides
+    Spike,
+    /// the mean of the following window jumps away from the one before
+    LevelShift,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    /// 1 based line in the input
+    pub line: usize,
+    pub kind: AnomalyKind,
+    /// how far past its threshold the anomaly is, 1.0 is right at the threshold
+    pub severity: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalyConfig {
+    /// readings used for the rolling medians and level comparison
+    pub window: usize,
+    /// distance from the rolling median that counts as a spike
+    pub spike: i32,
+    /// change of the window mean that counts as a level shift
+    pub shift: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig { window: 5, spike: 50, shift: 50.0 }
+    }
+}
+
+fn median(values: &[i32]) -> Option<i32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    sorted.get(sorted.len() / 2).copied()
+}
+
+/// Find dropouts, spikes and level shifts, ordered by line.
+pub fn detect_anomalies(input: &str, config: &AnomalyConfig) -> Vec<Anomaly> {
+    clean(input, config).1
+}
+
+/// Readings with dropouts and spikes removed, ready for [`part1`] and [`part2`], plus all anomalies found.
+pub fn clean(input: &str, config: &AnomalyConfig) -> (Data, Vec<Anomaly>) {
+    let w = config.window.max(1);
+    let lines = input.lines().map(|l| l.trim().parse::<i32>().ok()).collect::<Vec<_>>();
+    let mut anomalies = Vec::new();
+
+    // dropouts, every line of a gap gets the length of the gap
+    let mut gap = Vec::new();
+    for (i, l) in lines.iter().enumerate() {
+        if l.is_none() {
+            gap.push(i + 1);
+        }
+        if l.is_some() || i + 1 == lines.len() {
+            let severity = gap.len() as f64;
+            anomalies.extend(gap.drain(..).map(|line| Anomaly { line, kind: AnomalyKind::Dropout, severity }));
+        }
+    }
+
+    // spikes have to stand out against the readings before and after them,
+    // so the first reading after a level shift is not mistaken for one
+    let valid = lines.iter().enumerate().filter_map(|(i, l)| l.map(|d| (i + 1, d))).collect::<Vec<_>>();
+    let values = valid.iter().map(|(_, d)| *d).collect::<Vec<_>>();
+    let mut spikes = vec![false; valid.len()];
+    for (i, &(line, depth)) in valid.iter().enumerate() {
+        let before = median(&values[i.saturating_sub(w)..i]);
+        let after = median(&values[(i + 1).min(values.len())..(i + 1 + w).min(values.len())]);
+        let deviation = [before, after].iter().flatten().map(|m| (depth - m).abs()).min();
+        if let Some(d) = deviation.filter(|&d| d > config.spike) {
+            spikes[i] = true;
+            anomalies.push(Anomaly { line, kind: AnomalyKind::Spike, severity: d as f64 / config.spike as f64 });
+        }
+    }
+
+    // level shifts compare neighbouring window sums, only the strongest point of a shift is reported
+    let steady = valid.iter().zip(&spikes).filter(|(_, &s)| !s).map(|(v, _)| *v).collect::<Vec<_>>();
+    let sums = window_sums(&steady.iter().map(|(_, d)| *d).collect::<Vec<_>>(), w);
+    let mut best: Option<(usize, f64)> = None;
+    for i in w..sums.len() {
+        let change = (sums[i] - sums[i - w]).abs() as f64 / w as f64;
+        if change > config.shift {
+            if best.is_none_or(|(_, c)| change > c) {
+                best = Some((steady[i].0, change));
+            }
+        } else if let Some((line, c)) = best.take() {
+            anomalies.push(Anomaly { line, kind: AnomalyKind::LevelShift, severity: c / config.shift });
+        }
+    }
+    if let Some((line, c)) = best {
+        anomalies.push(Anomaly { line, kind: AnomalyKind::LevelShift, severity: c / config.shift });
+    }
+
+    anomalies.sort_by_key(|a| a.line);
+    let data = valid.iter().zip(&spikes).filter(|(_, &s)| !s).map(|((_, d), _)| *d).collect();
+    (data, anomalies)
+}
+
 #[cfg(test)]
 mod tests {
-    //use super::*;
+    use super::*;
 
-    const _SAMPLE: [i32; 10] = [199,200,208,210,200,207,240,269,260,263];
+    const SAMPLE: [i32; 10] = [199,200,208,210,200,207,240,269,260,263];
 
     #[test]
     pub fn test1() {
-        //assert_eq!(part1(&SAMPLE), 7);
-        //assert_eq!(part1_golf1(&SAMPLE), 7);
+        assert_eq!(part1(&SAMPLE.to_vec()), 7);
+        assert_eq!(part1_golf1(&SAMPLE.to_vec()), 7);
+        assert_eq!(depth_increases(&SAMPLE, 1), 7);
     }
 
     #[test]
     pub fn test2() {
-        //assert_eq!(part2(&SAMPLE), 5);
-        //assert_eq!(part2_golf1(&SAMPLE), 5);
+        assert_eq!(part2(&SAMPLE.to_vec()), 5);
+        assert_eq!(part2_golf1(&SAMPLE.to_vec()), 5);
+        assert_eq!(part2_window(&SAMPLE.to_vec()), 5);
+    }
+
+    #[test]
+    pub fn test_windows() {
+        assert_eq!(window_sums(&SAMPLE, 3), vec![607, 618, 618, 617, 647, 716, 769, 792]);
+        assert_eq!(depth_increases(&SAMPLE, 0), 0);
+        assert_eq!(depth_increases(&SAMPLE, 10), 0);
+        assert_eq!(depth_increases(&SAMPLE, 11), 0);
+        for window in 1..=10 {
+            assert_eq!(depth_increases(&SAMPLE, window), analyze(&SAMPLE, window).increases);
+        }
+    }
+
+    #[test]
+    pub fn test_analyze() {
+        assert_eq!(analyze(&SAMPLE, 1), SonarReport {
+            window: 1,
+            increases: 7,
+            positions: vec![1, 2, 3, 5, 6, 7, 9],
+            longest_run: 3,
+            largest_drop: Some((4, 10)),
+        });
+        assert_eq!(analyze(&SAMPLE, 3), SonarReport {
+            window: 3,
+            increases: 5,
+            positions: vec![1, 4, 5, 6, 7],
+            longest_run: 4,
+            largest_drop: Some((3, 1)),
+        });
+    }
+
+    #[test]
+    pub fn test_anomalies() {
+        let input = "100\n101\n102\n103\n104\n105\n900\n106\n107\n108\nabc\n\n109\n110\n300\n301\n302\n303\n304\n305";
+        let config = AnomalyConfig { window: 3, spike: 50, shift: 20.0 };
+        let (data, anomalies) = clean(input, &config);
+
+        let found = anomalies.iter().map(|a| (a.line, a.kind)).collect::<Vec<_>>();
+        assert_eq!(found, vec![
+            (7, AnomalyKind::Spike),
+            (11, AnomalyKind::Dropout),
+            (12, AnomalyKind::Dropout),
+            (15, AnomalyKind::LevelShift),
+        ]);
+        // closer to the median of the readings after it
+        assert_eq!(anomalies[0].severity, (900 - 107) as f64 / 50.0);
+        assert_eq!(anomalies[1].severity, 2.0);
+        assert!(anomalies[3].severity > 9.0);
+        assert_eq!(data.len(), 17);
+        assert_eq!(part1(&data), 16);
+
+        let sample = SAMPLE.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("\n");
+        assert_eq!(detect_anomalies(&sample, &AnomalyConfig::default()), vec![]);
+        assert_eq!(clean(&sample, &AnomalyConfig::default()).0, SAMPLE.to_vec());
+    }
+
+    #[test]
+    pub fn test_stream() {
+        for window in 0..=11 {
+            let mut stream = SonarStream::new(window);
+            stream.extend(SAMPLE);
+            let report = analyze(&SAMPLE, window);
+            assert_eq!(stream.increases(), report.increases);
+            assert_eq!(stream.longest_run(), report.longest_run);
+            assert_eq!(stream.largest_drop(), report.largest_drop);
+            assert_eq!(stream.window_sum(), window_sums(&SAMPLE, window).last().copied());
+        }
+
+        let mut stream = SonarStream::new(3);
+        stream.push(199);
+        assert_eq!(stream.window_sum(), None);
+        stream.read("200\n208\n210\n".as_bytes()).unwrap();
+        assert_eq!((stream.readings(), stream.window_sum(), stream.increases()), (4, Some(618), 1));
+        assert!(stream.read("200\nabc\n".as_bytes()).is_err());
+        assert_eq!(stream.readings(), 5);
     }
 }
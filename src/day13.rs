@@ -76,6 +76,18 @@ pub fn part2(inputs: &Data) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_fold_idempotent(points in prop::collection::hash_set((0..40, 0..40), 0..50), axis in prop_oneof![Just('x'), Just('y')], line in 1..20) {
+            let mut once = points;
+            fold((axis, line), &mut once);
+            let mut twice = once.clone();
+            fold((axis, line), &mut twice);
+            prop_assert_eq!(once, twice);
+        }
+    }
 
     #[test]
     pub fn test1() {
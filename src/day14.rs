@@ -1,3 +1,4 @@
+use crate::count::{BigUint, Count, Overflow, Solver, Width};
 
 type Data = (Vec<u8>, Vec<([u8;2], usize, usize)>, Vec<u64>);
 
@@ -60,6 +61,40 @@ pub fn step_n(base: Vec<u8>, num: Vec<u64>, rule: Vec<([u8;2], usize, usize)>, n
      occurences.iter().max().unwrap() - occurences.iter().filter(|&&x| x != 0).min().unwrap()
 }
 
+/// Pair counting like [`step_n`], generic over the counter so long polymers can use wider types.
+pub fn step_n_checked<C: Count>(base: &[u8], num: &[u64], rule: &[([u8;2], usize, usize)], n: u32) -> Result<C, Overflow> {
+    let mut num = num.iter().map(|&n| C::from_u64(n)).collect::<Vec<C>>();
+
+    for _ in 0..n {
+        let mut next = vec![C::zero(); rule.len()];
+        for (n, r) in num.iter().zip(rule) {
+            next[r.1] = next[r.1].try_add(n)?;
+            next[r.2] = next[r.2].try_add(n)?;
+        }
+        num = next;
+    }
+
+    let mut occurences = vec![C::zero(); 256];
+    occurences[*base.last().unwrap() as usize] = C::from_u64(1);
+    for (r, n) in rule.iter().zip(num) {
+        occurences[r.0[0] as usize] = occurences[r.0[0] as usize].try_add(&n)?;
+    }
+
+    let max = occurences.iter().max().unwrap();
+    let min = occurences.iter().filter(|&x| *x != C::zero()).min().unwrap();
+    max.try_sub(min)
+}
+
+pub fn step_n_width(inputs: &Data, n: u32, width: Width) -> Result<String, Overflow> {
+    struct Steps<'a>(&'a Data, u32);
+    impl Solver for Steps<'_> {
+        fn solve<C: Count>(&self) -> Result<C, Overflow> {
+            let (base, rule, num) = self.0;
+            step_n_checked(base, num, rule, self.1)
+        }
+    }
+    width.solve(&Steps(inputs, n))
+}
 
 #[aoc(day14, part1)]
 pub fn part1(inputs: &Data) -> u64 {
@@ -73,10 +108,54 @@ pub fn part2(inputs: &Data) -> u64 {
     step_n(base, num, rule, 40)
 }
 
+#[aoc(day14, part2, big)]
+pub fn part2_big(inputs: &Data) -> BigUint {
+    let (base, rule, num) = inputs;
+    step_n_checked(base, num, rule, 40).unwrap()
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    fn naive(template: &str, rules: &[(String, char)], n: i32) -> u64 {
+        let mut polymer = template.chars().collect::<Vec<_>>();
+        for _ in 0..n {
+            let mut next = vec![polymer[0]];
+            for w in polymer.windows(2) {
+                let pair = w.iter().collect::<String>();
+                next.push(rules.iter().find(|(f, _)| *f == pair).unwrap().1);
+                next.push(w[1]);
+            }
+            polymer = next;
+        }
+        let counts = polymer.iter().fold(HashMap::new(), |mut acc, c| {
+            *acc.entry(c).or_insert(0) += 1;
+            acc
+        });
+        (counts.values().max().unwrap() - counts.values().min().unwrap()) as u64
+    }
+
+    // template and a complete rule set over a small alphabet
+    fn polymer() -> impl Strategy<Value = (String, Vec<(String, char)>)> {
+        let letter = prop::sample::select(vec!['A', 'B', 'C']);
+        (prop::collection::vec(letter.clone(), 2..8), prop::collection::vec(letter, 9)).prop_map(|(template, inserts)| {
+            let pairs = "ABC".chars().cartesian_product("ABC".chars()).map(|(a, b)| format!("{}{}", a, b));
+            (template.into_iter().collect(), pairs.zip(inserts).collect())
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_naive((template, rules) in polymer(), n in 0..8i32) {
+            let input = format!("{}\n\n{}", template, rules.iter().map(|(f, t)| format!("{} -> {}", f, t)).join("\n"));
+            let (base, rule, num) = generator(&input);
+            prop_assert_eq!(step_n(base, num, rule, n), naive(&template, &rules, n));
+        }
+    }
 
     #[test]
     pub fn test1() {
@@ -125,5 +204,34 @@ CN -> C");
 
 
         assert_eq!(part2(&input), 2188189693529);
+        assert_eq!(part2_big(&input).to_string(), "2188189693529");
+    }
+
+    #[test]
+    pub fn test_checked() {
+        let input = generator("NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C");
+
+        assert_eq!(step_n_width(&input, 10, Width::U64), Ok("1588".to_string()));
+        assert_eq!(step_n_width(&input, 70, Width::U64), Err(Overflow));
+        assert_eq!(step_n_width(&input, 70, Width::U128), step_n_width(&input, 70, Width::Big));
+        assert_eq!(step_n_width(&input, 130, Width::U128), Err(Overflow));
+        assert!(step_n_width(&input, 130, Width::Big).is_ok());
     }
 }
@@ -19,6 +19,16 @@ impl Packet {
         Packet{v: 0, t: 0, data: 0, nsub: 0, sub: Vec::new()}
     }
 
+    pub fn literal(v: u64, data: u64) -> Self {
+        Packet{v, t: 4, data, nsub: 0, sub: Vec::new()}
+    }
+
+    /// Operator packet, `by_count` selects how [`Packet::encode`] writes the sub packets.
+    pub fn operator(v: u64, t: u64, sub: Vec<Packet>, by_count: bool) -> Self {
+        let nsub = if by_count { sub.len() as u32 } else { 0 };
+        Packet{v, t, data: 0, nsub, sub}
+    }
+
     pub fn version_sum(&self) -> u64 {
         self.v + self.sub.iter().map(|s| s.version_sum()).sum::<u64>() as u64
     }
@@ -48,6 +58,32 @@ impl Packet {
             _ => unreachable!()
         }
     }
+
+    /// Encode the packet back into bits. Operators with `nsub` set are written with the
+    /// sub packet count, all others with the total length of their sub packets.
+    pub fn encode(&self) -> Data {
+        let mut bits = to_bin(self.v, 3);
+        bits.append(&mut to_bin(self.t, 3));
+        if self.t == 4 {
+            // groups of 4 bits, each prefixed with 1 except for the last one
+            let groups = (64 - self.data.leading_zeros() as usize).div_ceil(4);
+            for g in (0..groups.max(1)).rev() {
+                bits.push(if g > 0 { '1' } else { '0' });
+                bits.append(&mut to_bin(self.data >> (4 * g), 4));
+            }
+        } else {
+            let mut sub = self.sub.iter().flat_map(|s| s.encode()).collect::<Data>();
+            if self.nsub > 0 {
+                bits.push('1');
+                bits.append(&mut to_bin(self.nsub as u64, 11));
+            } else {
+                bits.push('0');
+                bits.append(&mut to_bin(sub.len() as u64, 15));
+            }
+            bits.append(&mut sub);
+        }
+        bits
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -62,10 +98,9 @@ pub enum State {
     Finished,
 }
 
-#[aoc_generator(day16)]
-pub fn generator(input: &str) -> Data {
-    let binary = hex::decode(input).unwrap();
-    binary.into_iter().flat_map(|x| {
+pub fn parse(input: &str) -> Result<Data, hex::FromHexError> {
+    let binary = hex::decode(input)?;
+    Ok(binary.into_iter().flat_map(|x| {
         (0..8).rev().into_iter().map(|n|{
             if (1 << n) & x > 0 {
                 '1'
@@ -73,8 +108,27 @@ pub fn generator(input: &str) -> Data {
                 '0'
             }
         }).collect::<Vec<char>>()
-    }).collect::<Vec<char>>()
+    }).collect::<Vec<char>>())
+}
 
+#[aoc_generator(day16)]
+pub fn generator(input: &str) -> Data {
+    parse(input).unwrap()
+}
+
+/// Inverse of [`generator`], pads the bits with zeros to full bytes.
+pub fn to_hex(bits: &[char]) -> String {
+    let bytes = bits.chunks(8).map(|c| {
+        let mut byte = c.to_vec();
+        byte.resize(8, '0');
+        to_num(&byte) as u8
+    }).collect::<Vec<u8>>();
+    hex::encode_upper(bytes)
+}
+
+/// The lowest `len` bits of `num`, most significant first.
+pub fn to_bin(num: u64, len: usize) -> Data {
+    (0..len).rev().map(|n| if (num >> n) & 1 == 1 { '1' } else { '0' }).collect()
 }
 
 pub fn to_num(bin: &[char]) -> u64 {
@@ -134,8 +188,8 @@ pub fn state_machine(inputs: &Data, mut pos: &mut usize, initial: State) -> Pack
             ParseSubN => {
                 let n = to_num(&inputs[*pos..*pos+11]);
                 *pos += 11;
-                for i in 1..=n {
-                    println!("Recusing for {}/{} n packets at pos {}", i, n, *pos);
+                p.nsub = n as u32;
+                for _ in 1..=n {
                     let ps = state_machine(inputs, &mut pos, Init);
                     p.sub.push(ps);
                 }
@@ -145,8 +199,7 @@ pub fn state_machine(inputs: &Data, mut pos: &mut usize, initial: State) -> Pack
                 let len = to_num(&inputs[*pos..*pos+15]);
                 *pos += 15;
                 let target = *pos + len as usize;
-                for i in 1.. {
-                    println!("Recusing for {} -- {} len packets at pos {}", i, len, *pos);
+                loop {
                     let ps = state_machine(inputs, &mut pos, Init);
                     p.sub.push(ps);
                     if *pos == target {
@@ -170,9 +223,6 @@ pub fn part1(inputs: &Data) -> u64 {
 
     let p = state_machine(inputs, &mut pos, Init);
 
-    print!("Packet V {} T {} Data {}", p.v, p.t, p.data);
-    print!("{:?}", p.sub);
-
     p.version_sum()
 }
 
@@ -183,9 +233,6 @@ pub fn part2(inputs: &Data) -> u64 {
 
     let p = state_machine(inputs, &mut pos, Init);
 
-    print!("Packet V {} T {} Data {}", p.v, p.t, p.data);
-    print!("{:?}", p.sub);
-
     p.eval()
 }
 
@@ -193,6 +240,33 @@ pub fn part2(inputs: &Data) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    fn packet() -> impl Strategy<Value = Packet> {
+        let literal = (0..8u64, any::<u64>()).prop_map(|(v, data)| Packet::literal(v, data));
+        literal.prop_recursive(3, 24, 4, |inner| {
+            (0..8u64, (0..8u64).prop_filter("literal", |t| *t != 4), prop::collection::vec(inner, 1..=4), any::<bool>())
+                .prop_map(|(v, t, sub, by_count)| Packet::operator(v, t, sub, by_count))
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_roundtrip(p in packet()) {
+            let input = generator(&to_hex(&p.encode()));
+            let mut pos = 0;
+            prop_assert_eq!(state_machine(&input, &mut pos, Init), p);
+        }
+    }
+
+    #[test]
+    pub fn test_encode() {
+        for hex in ["D2FE28", "38006F45291200", "EE00D40C823060"] {
+            let input = generator(hex);
+            let p = state_machine(&input, &mut 0, Init);
+            assert_eq!(to_hex(&p.encode()), hex);
+        }
+    }
 
     #[test]
     pub fn test1() {
@@ -1,11 +1,16 @@
 use itertools::Itertools;
+use scan_fmt::parse::ScanError;
 use scan_fmt::scan_fmt;
 
 type Data = (i32, i32, i32, i32);
 
+pub fn parse(input: &str) -> Result<Data, ScanError> {
+    scan_fmt!(input, "target area: x={}..{}, y={}..{}", i32, i32, i32, i32)
+}
+
 #[aoc_generator(day17)]
 pub fn generator(input: &str) -> Data {
-    scan_fmt!(input, "target area: x={}..{}, y={}..{}", i32, i32, i32, i32).unwrap()
+    parse(input).unwrap()
 }
 
 pub fn check_trajectory(target: &Data, svs: &(i32, i32)) -> Option<i32> {
@@ -182,6 +182,46 @@ pub fn part2(inputs: &Data) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    fn element(depth: u32) -> BoxedStrategy<Element> {
+        let value = (0..=9u8).prop_map(Element::Value);
+        if depth == 0 {
+            return value.boxed();
+        }
+        prop_oneof![
+            value,
+            (element(depth - 1), element(depth - 1)).prop_map(|(l, r)| Element::Pair(Box::new(l), Box::new(r))),
+        ].boxed()
+    }
+
+    // a valid snailfish number is a pair nested at most four deep
+    fn number() -> impl Strategy<Value = Element> {
+        (element(3), element(3)).prop_map(|(l, r)| Element::Pair(Box::new(l), Box::new(r)))
+    }
+
+    fn max_depth(e: &Element) -> usize {
+        match e {
+            Element::Value(_) => 0,
+            Element::Pair(l, r) => 1 + max_depth(l).max(max_depth(r)),
+        }
+    }
+
+    fn max_value(e: &Element) -> u8 {
+        match e {
+            Element::Value(v) => *v,
+            Element::Pair(l, r) => max_value(l).max(max_value(r)),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_reduced(inputs in prop::collection::vec(number(), 1..8)) {
+            let e = sum(&inputs);
+            prop_assert!(max_depth(&e) <= 4, "{}", e);
+            prop_assert!(max_value(&e) < 10, "{}", e);
+        }
+    }
 
     #[test]
     pub fn test_parse() {
@@ -1,50 +1,531 @@
-type Data = Vec<Instruction>;
+use std::fmt::{Display, Formatter};
 
-pub struct Instruction {
-    direction: char,
-    distance: i32,
+type Data = Vec<Command>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Forward(i32),
+    Down(i32),
+    Up(i32),
+    // extended dialect only
+    Back(i32),
+    /// move forward without changing depth, ignoring the aim
+    Hold(i32),
+    ResetAim,
+    /// turn by the given degrees, a multiple of 90, only affects [Spatial] navigation
+    Left(i32),
+    Right(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// `forward`, `down` and `up` as in the puzzle
+    Standard,
+    /// additionally `back`, `hold`, `reset-aim`, `left` and `right`
+    Extended,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnknownCommand(String),
+    /// a known command that is not part of the requested dialect
+    Unsupported(String),
+    MissingDistance,
+    InvalidDistance(String),
+    NegativeDistance(i32),
+    /// turns have to be multiples of 90 degrees
+    InvalidAngle(i32),
+    UnexpectedArgument(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1 based line in the input
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::UnknownCommand(c) => write!(f, "unknown command {}", c),
+            ParseErrorKind::Unsupported(c) => write!(f, "{} needs the extended dialect", c),
+            ParseErrorKind::MissingDistance => write!(f, "missing distance"),
+            ParseErrorKind::InvalidDistance(d) => write!(f, "invalid distance {}", d),
+            ParseErrorKind::NegativeDistance(d) => write!(f, "negative distance {}", d),
+            ParseErrorKind::InvalidAngle(a) => write!(f, "invalid angle {}, expected a multiple of 90", a),
+            ParseErrorKind::UnexpectedArgument(a) => write!(f, "unexpected argument {}", a),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Command {
+    /// Parse a single command, names are case insensitive.
+    pub fn parse(line: &str, dialect: Dialect) -> Result<Command, ParseErrorKind> {
+        let mut parts = line.split_whitespace();
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let argument = parts.next();
+        if let Some(extra) = parts.next() {
+            return Err(ParseErrorKind::UnexpectedArgument(extra.to_string()));
+        }
+
+        let distance = || -> Result<i32, ParseErrorKind> {
+            let arg = argument.ok_or(ParseErrorKind::MissingDistance)?;
+            match arg.parse::<i32>() {
+                Ok(d) if d < 0 => Err(ParseErrorKind::NegativeDistance(d)),
+                Ok(d) => Ok(d),
+                Err(_) => Err(ParseErrorKind::InvalidDistance(arg.to_string())),
+            }
+        };
+
+        let command = match name.as_str() {
+            "forward" => Command::Forward(distance()?),
+            "down" => Command::Down(distance()?),
+            "up" => Command::Up(distance()?),
+            "back" => Command::Back(distance()?),
+            "hold" => Command::Hold(distance()?),
+            "left" | "right" => match distance()? {
+                a if a % 90 != 0 => return Err(ParseErrorKind::InvalidAngle(a)),
+                a if name == "left" => Command::Left(a),
+                a => Command::Right(a),
+            },
+            "reset-aim" => match argument {
+                Some(arg) => return Err(ParseErrorKind::UnexpectedArgument(arg.to_string())),
+                None => Command::ResetAim,
+            },
+            _ => return Err(ParseErrorKind::UnknownCommand(name)),
+        };
+
+        match command {
+            Command::Back(_) | Command::Hold(_) | Command::ResetAim | Command::Left(_) | Command::Right(_) if dialect == Dialect::Standard => {
+                Err(ParseErrorKind::Unsupported(name))
+            }
+            c => Ok(c),
+        }
+    }
+}
+
+pub fn parse(input: &str, dialect: Dialect) -> Result<Data, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| Command::parse(l, dialect).map_err(|kind| ParseError { line: i + 1, kind }))
+        .collect()
 }
 
 #[aoc_generator(day2)]
 pub fn generator(input: &str) -> Data {
-    input.lines().map(|l|{
-        Instruction { direction: l.chars().next().unwrap(), distance: l.split(' ').skip(1).next().unwrap().parse::<i32>().unwrap()}
-    }).collect()
+    parse(input, Dialect::Standard).unwrap()
+}
+
+/// Position and aim of the submarine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Submarine {
+    pub horizontal: i32,
+    pub depth: i32,
+    pub aim: i32,
+}
+
+impl Submarine {
+    pub fn product(&self) -> i32 {
+        self.horizontal * self.depth
+    }
+}
+
+/// State a [NavigationModel] moves around, exportable as a trajectory.
+pub trait NavigationState: Copy + Default {
+    /// CSV header of [NavigationState::row]
+    const COLUMNS: &'static str;
+
+    fn row(&self) -> String;
+
+    /// Horizontal position and depth, the submarine seen from the side.
+    fn profile(&self) -> (i32, i32);
+}
+
+impl NavigationState for Submarine {
+    const COLUMNS: &'static str = "horizontal,depth,aim";
+
+    fn row(&self) -> String {
+        format!("{},{},{}", self.horizontal, self.depth, self.aim)
+    }
+
+    fn profile(&self) -> (i32, i32) {
+        (self.horizontal, self.depth)
+    }
+}
+
+/// How a command changes the submarine.
+pub trait NavigationModel {
+    type State: NavigationState;
+
+    fn apply(&self, state: &mut Self::State, command: Command);
+
+    /// Limits checked after every command, `step` being its 1 based index.
+    fn check(&self, _step: usize, _state: &Self::State) -> Result<(), NavigationError> {
+        Ok(())
+    }
+}
+
+/// Final state after all commands, starting at the surface.
+pub fn navigate<M: NavigationModel + ?Sized>(model: &M, inputs: &[Command]) -> Result<M::State, NavigationError> {
+    inputs.iter().enumerate().try_fold(M::State::default(), |mut state, (i, &c)| {
+        model.apply(&mut state, c);
+        model.check(i + 1, &state).map(|_| state)
+    })
+}
+
+/// part1, `down` and `up` change the depth directly.
+pub struct Direct;
+
+impl NavigationModel for Direct {
+    type State = Submarine;
+
+    fn apply(&self, sub: &mut Submarine, command: Command) {
+        match command {
+            Command::Up(x) => sub.depth -= x,
+            Command::Down(x) => sub.depth += x,
+            Command::Forward(x) | Command::Hold(x) => sub.horizontal += x,
+            Command::Back(x) => sub.horizontal -= x,
+            Command::ResetAim | Command::Left(_) | Command::Right(_) => {}
+        }
+    }
+}
+
+/// part2, `down` and `up` change the aim and moving changes the depth by aim * distance.
+pub struct Aimed;
+
+impl NavigationModel for Aimed {
+    type State = Submarine;
+
+    fn apply(&self, sub: &mut Submarine, command: Command) {
+        match command {
+            Command::Up(x) => sub.aim -= x,
+            Command::Down(x) => sub.aim += x,
+            Command::Forward(x) => {
+                sub.horizontal += x;
+                sub.depth += sub.aim * x;
+            }
+            Command::Back(x) => {
+                sub.horizontal -= x;
+                sub.depth -= sub.aim * x;
+            }
+            Command::Hold(x) => sub.horizontal += x,
+            Command::ResetAim => sub.aim = 0,
+            Command::Left(_) | Command::Right(_) => {}
+        }
+    }
+}
+
+/// Every state of the submarine, starting at the surface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trajectory<S = Submarine> {
+    pub positions: Vec<S>,
+}
+
+impl<S: NavigationState> Trajectory<S> {
+    /// Fails at the first command that breaks the model's limits.
+    pub fn record<M: NavigationModel<State = S> + ?Sized>(model: &M, inputs: &[Command]) -> Result<Trajectory<S>, NavigationError> {
+        let mut state = S::default();
+        let mut positions = vec![state];
+        for (i, &c) in inputs.iter().enumerate() {
+            model.apply(&mut state, c);
+            model.check(i + 1, &state)?;
+            positions.push(state);
+        }
+        Ok(Trajectory { positions })
+    }
+
+    pub fn last(&self) -> S {
+        *self.positions.last().unwrap()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("step,{}\n", S::COLUMNS);
+        for (i, p) in self.positions.iter().enumerate() {
+            csv += &format!("{},{}\n", i, p.row());
+        }
+        csv
+    }
+
+    /// Depth profile as a polyline scaled into a `width` x `height` image, depth growing downwards.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        let profile = self.positions.iter().map(|p| p.profile()).collect::<Vec<_>>();
+        let (min_x, max_x) = profile.iter().map(|p| p.0).fold((0, 0), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        let (min_y, max_y) = profile.iter().map(|p| p.1).fold((0, 0), |(lo, hi), y| (lo.min(y), hi.max(y)));
+        let scale_x = width as f64 / (max_x - min_x).max(1) as f64;
+        let scale_y = height as f64 / (max_y - min_y).max(1) as f64;
+        let points = profile.iter()
+            .map(|&(x, y)| format!("{:.1},{:.1}", (x - min_x) as f64 * scale_x, (y - min_y) as f64 * scale_y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+             <polyline fill=\"none\" stroke=\"black\" points=\"{}\"/>\n</svg>\n",
+            points, w = width, h = height
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heading {
+    East,
+    North,
+    West,
+    South,
+}
+
+impl Heading {
+    /// Turn counterclockwise by the given number of quarter turns.
+    pub fn turn(self, quarters: i32) -> Heading {
+        const ORDER: [Heading; 4] = [Heading::East, Heading::North, Heading::West, Heading::South];
+        ORDER[(self as i32 + quarters).rem_euclid(4) as usize]
+    }
+
+    /// Unit step in (x, y).
+    pub fn step(self) -> (i32, i32) {
+        match self {
+            Heading::East => (1, 0),
+            Heading::North => (0, 1),
+            Heading::West => (-1, 0),
+            Heading::South => (0, -1),
+        }
+    }
+}
+
+/// 3D position of the submarine, x growing along the initial heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pose {
+    pub x: i32,
+    pub y: i32,
+    pub depth: i32,
+    pub heading: Heading,
+    /// depth change per unit moved, the aim of part2
+    pub pitch: i32,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Pose { x: 0, y: 0, depth: 0, heading: Heading::East, pitch: 0 }
+    }
+}
+
+impl NavigationState for Pose {
+    const COLUMNS: &'static str = "x,y,depth,heading,pitch";
+
+    fn row(&self) -> String {
+        format!("{},{},{},{:?},{}", self.x, self.y, self.depth, self.heading, self.pitch)
+    }
+
+    fn profile(&self) -> (i32, i32) {
+        (self.x, self.depth)
+    }
+}
+
+/// What `up` and `down` do in [Spatial] navigation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vertical {
+    /// move straight up or down, as in part1
+    Direct,
+    /// change the pitch, as the aim in part2
+    Pitch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationError {
+    /// `step` is the 1 based index of the offending command
+    TooDeep { step: usize, depth: i32, limit: i32 },
+    AboveSurface { step: usize, depth: i32 },
+}
+
+impl Display for NavigationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NavigationError::TooDeep { step, depth, limit } => write!(f, "step {}: depth {} exceeds the limit of {}", step, depth, limit),
+            NavigationError::AboveSurface { step, depth } => write!(f, "step {}: depth {} is above the surface", step, depth),
+        }
+    }
+}
+
+impl std::error::Error for NavigationError {}
+
+/// Navigation with heading and pitch, checking the depth after every command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spatial {
+    pub vertical: Vertical,
+    pub max_depth: Option<i32>,
+    /// fail when the submarine would leave the water
+    pub surface: bool,
+}
+
+impl Spatial {
+    pub fn new(vertical: Vertical) -> Self {
+        Spatial { vertical, max_depth: None, surface: false }
+    }
+
+    pub fn with_max_depth(mut self, limit: i32) -> Self {
+        self.max_depth = Some(limit);
+        self
+    }
+
+    pub fn with_surface(mut self) -> Self {
+        self.surface = true;
+        self
+    }
+}
+
+impl NavigationModel for Spatial {
+    type State = Pose;
+
+    fn apply(&self, pose: &mut Pose, command: Command) {
+        let travel = |pose: &mut Pose, x: i32, dive: bool| {
+            let (dx, dy) = pose.heading.step();
+            pose.x += dx * x;
+            pose.y += dy * x;
+            if dive {
+                pose.depth += pose.pitch * x;
+            }
+        };
+        match (command, self.vertical) {
+            (Command::Up(x), Vertical::Direct) => pose.depth -= x,
+            (Command::Down(x), Vertical::Direct) => pose.depth += x,
+            (Command::Up(x), Vertical::Pitch) => pose.pitch -= x,
+            (Command::Down(x), Vertical::Pitch) => pose.pitch += x,
+            (Command::Forward(x), _) => travel(pose, x, true),
+            (Command::Back(x), _) => travel(pose, -x, true),
+            (Command::Hold(x), _) => travel(pose, x, false),
+            (Command::ResetAim, _) => pose.pitch = 0,
+            (Command::Left(a), _) => pose.heading = pose.heading.turn(a / 90),
+            (Command::Right(a), _) => pose.heading = pose.heading.turn(-a / 90),
+        }
+    }
+
+    fn check(&self, step: usize, pose: &Pose) -> Result<(), NavigationError> {
+        match self.max_depth {
+            Some(limit) if pose.depth > limit => Err(NavigationError::TooDeep { step, depth: pose.depth, limit }),
+            _ if self.surface && pose.depth < 0 => Err(NavigationError::AboveSurface { step, depth: pose.depth }),
+            _ => Ok(()),
+        }
+    }
 }
 
 #[aoc(day2, part1)]
 pub fn part1(inputs: &Data) -> i32 {
-    let (depth, distance) = inputs
-        .iter()
-        .fold((0, 0), |mut acc, i| {
-            let change = match i.direction {
-                'u' => (0, -1 * i.distance),
-                'd' => (0, 1 * i.distance),
-                'f' => (1 * i.distance, 0),
-                _ => panic!("unknown op {}", i.direction)
-            };
-            acc.0 += change.0;
-            acc.1 += change.1;
-            acc
-        });
-    depth * distance
+    navigate(&Direct, inputs).unwrap().product()
 }
 
 #[aoc(day2, part2)]
 pub fn part2(inputs: &Data) -> i32 {
-    let (depth, distance, _) = inputs
-        .iter()
-        .fold((0, 0, 0), |mut acc, i| {
-            let change = match i.direction {
-                'u' => (0, 0, -1 * i.distance),
-                'd' => (0, 0, 1 * i.distance),
-                'f' => (acc.2 * i.distance, 1 * i.distance, 0),
-                _ => panic!("unknown op {}", i.direction)
-            };
-            acc.0 += change.0;
-            acc.1 += change.1;
-            acc.2 += change.2;
-            acc
-        });
-    depth * distance
+    navigate(&Aimed, inputs).unwrap().product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
+    #[test]
+    pub fn test1() {
+        assert_eq!(part1(&generator(SAMPLE)), 150);
+    }
+
+    #[test]
+    pub fn test2() {
+        assert_eq!(part2(&generator(SAMPLE)), 900);
+    }
+
+    #[test]
+    pub fn test_parse() {
+        assert_eq!(parse("Forward 5\nDOWN 3\n", Dialect::Standard), Ok(vec![Command::Forward(5), Command::Down(3)]));
+
+        let errors = [
+            ("forward 5\nsideways 3", 2, ParseErrorKind::UnknownCommand("sideways".to_string())),
+            ("up", 1, ParseErrorKind::MissingDistance),
+            ("up x", 1, ParseErrorKind::InvalidDistance("x".to_string())),
+            ("down -4", 1, ParseErrorKind::NegativeDistance(-4)),
+            ("down 4 5", 1, ParseErrorKind::UnexpectedArgument("5".to_string())),
+            ("forward 1\nback 2", 2, ParseErrorKind::Unsupported("back".to_string())),
+        ];
+        for (input, line, kind) in errors {
+            assert_eq!(parse(input, Dialect::Standard), Err(ParseError { line, kind }));
+        }
+        assert_eq!(parse("reset-aim 3", Dialect::Extended).unwrap_err().to_string(), "line 1: unexpected argument 3");
+    }
+
+    #[test]
+    pub fn test_extended() {
+        let input = parse("down 5\nforward 8\nreset-aim\nhold 4\nBack 2\ndown 1\nback 1", Dialect::Extended).unwrap();
+        assert_eq!(input[2..5], [Command::ResetAim, Command::Hold(4), Command::Back(2)]);
+        // horizontal 8 + 4 - 2 - 1, depth 40 - 1
+        assert_eq!(part2(&input), 9 * 39);
+        assert_eq!(part1(&input), 9 * 6);
+    }
+
+    #[test]
+    pub fn test_trajectory() {
+        let trajectory = Trajectory::record(&Aimed, &generator(SAMPLE)).unwrap();
+        assert_eq!(trajectory.positions.len(), 7);
+        assert_eq!(trajectory.last(), Submarine { horizontal: 15, depth: 60, aim: 10 });
+        assert_eq!(Ok(trajectory.last()), navigate(&Aimed, &generator(SAMPLE)));
+
+        let csv = trajectory.to_csv();
+        assert!(csv.starts_with("step,horizontal,depth,aim\n0,0,0,0\n1,5,0,0\n2,5,0,5\n"));
+        assert!(csv.ends_with("6,15,60,10\n"));
+
+        let svg = trajectory.to_svg(150, 60);
+        assert!(svg.contains("points=\"0.0,0.0 50.0,0.0 50.0,0.0 130.0,40.0 "));
+        assert!(svg.ends_with("150.0,60.0\"/>\n</svg>\n"));
+    }
+
+    #[test]
+    pub fn test_models() {
+        // models can be swapped at runtime
+        let models: [&dyn NavigationModel<State = Submarine>; 2] = [&Direct, &Aimed];
+        let products = models.iter().map(|m| navigate(*m, &generator(SAMPLE)).unwrap().product()).collect::<Vec<_>>();
+        assert_eq!(products, vec![150, 900]);
+    }
+
+    #[test]
+    pub fn test_spatial() {
+        // part1 and part2 are the special cases without turns
+        let input = generator(SAMPLE);
+        let direct = navigate(&Spatial::new(Vertical::Direct), &input).unwrap();
+        assert_eq!(direct.x * direct.depth, part1(&input));
+        let pitched = navigate(&Spatial::new(Vertical::Pitch), &input).unwrap();
+        assert_eq!(pitched.x * pitched.depth, part2(&input));
+        assert_eq!(pitched.pitch, 10);
+
+        let input = parse("down 1\nforward 4\nleft 90\nforward 2\nright 270\nback 3\nhold 1", Dialect::Extended).unwrap();
+        let pose = navigate(&Spatial::new(Vertical::Pitch), &input).unwrap();
+        assert_eq!(pose, Pose { x: 4 + 3 - 1, y: 2, depth: 4 + 2 - 3, heading: Heading::West, pitch: 1 });
+        let trajectory = Trajectory::record(&Spatial::new(Vertical::Pitch), &input).unwrap();
+        assert_eq!(trajectory.positions.len(), 8);
+        assert!(trajectory.to_csv().starts_with("step,x,y,depth,heading,pitch\n0,0,0,0,East,0\n1,0,0,0,East,1\n2,4,0,4,East,1\n3,4,0,4,North,1\n"));
+        // side view, x from 0 to 7 and depth from 0 to 6
+        assert!(trajectory.to_svg(60, 60).contains("points=\"0.0,0.0 0.0,0.0 34.3,40.0 34.3,40.0 34.3,60.0 34.3,60.0 60.0,30.0 51.4,30.0\""));
+        assert_eq!(parse("left 45", Dialect::Extended), Err(ParseError { line: 1, kind: ParseErrorKind::InvalidAngle(45) }));
+    }
+
+    #[test]
+    pub fn test_limits() {
+        let input = generator(SAMPLE);
+        let model = Spatial::new(Vertical::Pitch).with_max_depth(50);
+        assert_eq!(navigate(&model, &input), Err(NavigationError::TooDeep { step: 6, depth: 60, limit: 50 }));
+        assert_eq!(Trajectory::record(&model, &input).unwrap_err().to_string(), "step 6: depth 60 exceeds the limit of 50");
+        assert!(navigate(&Spatial::new(Vertical::Pitch).with_max_depth(60).with_surface(), &input).is_ok());
+
+        let input = parse("forward 2\nup 1\nforward 1", Dialect::Standard).unwrap();
+        let model = Spatial::new(Vertical::Pitch).with_surface();
+        assert_eq!(navigate(&model, &input), Err(NavigationError::AboveSurface { step: 3, depth: -1 }));
+    }
 }
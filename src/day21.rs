@@ -1,5 +1,7 @@
 use std::collections::{HashMap};
+use std::fmt::{Display, Formatter};
 use itertools::iproduct;
+use crate::count::{BigUint, Count, Overflow, Solver, Width};
 
 const END_SCORE: u8 = 21;
 
@@ -101,15 +103,113 @@ pub fn play_game_naive(cache: &mut Cache, pos1: u8, pos2: u8, score1: u8, score2
     wins
 }
 
+/// Sums of three dirac dice rolls with the number of universes producing them.
+pub fn dirac_rolls() -> Vec<(u8, u64)> {
+    let map = iproduct!([1,2,3], [1,2,3], [1,2,3]).fold(HashMap::<u8, u64>::new(), |mut acc, (x,y,z)| {
+        *acc.entry(x+y+z).or_insert(0) += 1;
+        acc
+    });
+    map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<(u8, u64)>>()
+}
+
+/// Most states a [`Game`] may keep at once, each holding two counts.
+pub const MAX_STATES: u64 = 1 << 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    EmptyBoard,
+    /// board and end score need more than [`MAX_STATES`] states
+    TooManyStates { states: u64 },
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::EmptyBoard => write!(f, "the board needs at least one field"),
+            GameError::TooManyStates { states } => write!(f, "{} states needed, at most {} are supported", states, MAX_STATES),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// Dirac dice game on a board of any size and up to any score, counted with checked arithmetic.
+pub struct Game {
+    board: usize,
+    end_score: usize,
+    rolls: Vec<(u8, u64)>,
+}
+
+impl Game {
+    /// Keeps `board + 1` layers of `end_score * board^2` states, which is limited to [`MAX_STATES`].
+    pub fn new(board: u8, end_score: u16) -> Result<Self, GameError> {
+        if board == 0 {
+            return Err(GameError::EmptyBoard);
+        }
+        let b = board as u64;
+        let states = (b + 1) * b * b * end_score as u64;
+        if states > MAX_STATES {
+            return Err(GameError::TooManyStates { states });
+        }
+        Ok(Game { board: board as usize, end_score: end_score as usize, rolls: dirac_rolls() })
+    }
+
+    /// Universes won by each player. Positions are taken modulo the board, 0 being the last field.
+    pub fn wins<C: Count>(&self, pos1: u8, pos2: u8) -> Result<(C, C), Overflow> {
+        let (board, end) = (self.board, self.end_score);
+        if end == 0 {
+            return Ok((C::from_u64(1), C::zero()));
+        }
+        let field = |pos: u8| (pos as usize + board - 1) % board;
+        let index = |score: usize, pos1: usize, pos2: usize| (score * board + pos1) * board + pos2;
+        let rolls = self.rolls.iter().map(|&(advance, multi)| (advance as usize, C::from_u64(multi))).collect::<Vec<_>>();
+
+        // wins of the player to move and of the other one, by the sum of both scores. Every turn
+        // adds between 1 and `board` points, so going down from the highest sums only needs the
+        // last `board` layers.
+        let mut layers: Vec<Vec<(C, C)>> = vec![Vec::new(); board + 1];
+        for total in (0..=2 * (end - 1)).rev() {
+            let mut layer = vec![(C::zero(), C::zero()); end * board * board];
+            for score1 in total.saturating_sub(end - 1)..=total.min(end - 1) {
+                let score2 = total - score1;
+                for (pos1, pos2) in iproduct!(0..board, 0..board) {
+                    let mut wins = (C::zero(), C::zero());
+                    for (advance, multi) in &rolls {
+                        let next = (pos1 + advance) % board;
+                        let score = score1 + next + 1;
+                        if score >= end {
+                            wins.0 = wins.0.try_add(multi)?;
+                        } else {
+                            // swap players for next turn
+                            let (uw1, uw2) = &layers[(total + next + 1) % (board + 1)][index(score2, pos2, next)];
+                            wins.0 = wins.0.try_add(&uw2.try_mul(multi)?)?;
+                            wins.1 = wins.1.try_add(&uw1.try_mul(multi)?)?;
+                        }
+                    }
+                    layer[index(score1, pos1, pos2)] = wins;
+                }
+            }
+            layers[total % (board + 1)] = layer;
+        }
+        Ok(layers[0][index(0, field(pos1), field(pos2))].clone())
+    }
+
+    /// Universes the more successful player wins in.
+    pub fn wins_width(&self, pos1: u8, pos2: u8, width: Width) -> Result<String, Overflow> {
+        struct MostWins<'a>(&'a Game, u8, u8);
+        impl Solver for MostWins<'_> {
+            fn solve<C: Count>(&self) -> Result<C, Overflow> {
+                let (u1, u2) = self.0.wins::<C>(self.1, self.2)?;
+                Ok(u1.max(u2))
+            }
+        }
+        width.solve(&MostWins(self, pos1, pos2))
+    }
+}
+
 #[aoc(day21, part2, cache)]
 pub fn part2_cache(inputs: &Data) -> u64 {
-    let rolls = {
-        let map = iproduct!([1,2,3], [1,2,3], [1,2,3]).fold(HashMap::<u8, u64>::new(), |mut acc, (x,y,z)| {
-            *acc.entry(x+y+z).or_insert(0) += 1;
-            acc
-        });
-        map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<(u8, u64)>>()
-    };
+    let rolls = dirac_rolls();
 
     let mut success_lookups = 0;
     let mut iterations = 0;
@@ -141,6 +241,11 @@ pub fn part2_cache_naive(inputs: &Data) -> u64 {
     u1.max(u2)
 }
 
+#[aoc(day21, part2, big)]
+pub fn part2_big(inputs: &Data) -> BigUint {
+    let (u1, u2) = Game::new(10, END_SCORE as u16).unwrap().wins::<BigUint>(inputs[0].pos, inputs[1].pos).unwrap();
+    u1.max(u2)
+}
 
 #[cfg(test)]
 mod tests {
@@ -158,6 +263,32 @@ mod tests {
         assert_eq!(part2_cache(&vec![Player{score: 0, pos: 1}, Player{score: 0, pos: 6}]), 157253621231420);
     }
 
+    #[test]
+    pub fn test2_big() {
+        assert_eq!(part2_big(&vec![Player{score: 0, pos: 4}, Player{score: 0, pos: 8}]).to_string(), "444356092776315");
+        assert_eq!(part2_big(&vec![Player{score: 0, pos: 1}, Player{score: 0, pos: 6}]).to_string(), "157253621231420");
+    }
+
+    #[test]
+    pub fn test2_checked() {
+        let game = Game::new(10, 40).unwrap();
+        assert_eq!(game.wins_width(4, 8, Width::U64), Err(Overflow));
+        assert_eq!(game.wins_width(4, 8, Width::U128), game.wins_width(4, 8, Width::Big));
+        assert!(Game::new(20, 21).unwrap().wins_width(4, 8, Width::U64).is_ok());
+        // positions wrap around the board
+        assert_eq!(Game::new(10, 21).unwrap().wins_width(14, 18, Width::U64), Ok("444356092776315".to_string()));
+        assert_eq!(Game::new(10, 0).unwrap().wins::<u64>(4, 8), Ok((1, 0)));
+        // a single field scores one point per turn, the first player wins after five turns in total
+        assert_eq!(Game::new(1, 3).unwrap().wins::<u64>(1, 1), Ok((27u64.pow(5), 0)));
+    }
+
+    #[test]
+    pub fn test_game_limits() {
+        assert_eq!(Game::new(0, 21).err(), Some(GameError::EmptyBoard));
+        assert_eq!(Game::new(10, u16::MAX).err(), Some(GameError::TooManyStates { states: 11 * 100 * u16::MAX as u64 }));
+        assert!(Game::new(10, 15000).is_ok());
+    }
+
     #[test]
     pub fn test2_cache_naive() {
         assert_eq!(part2_cache_naive(&vec![Player{score: 0, pos: 4}, Player{score: 0, pos: 8}]), 444356092776315);
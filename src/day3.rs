@@ -1,60 +1,271 @@
-#[aoc_generator(day3)]
-pub fn generator(input: &str) -> Vec<String> {
-    input.lines().map(|l| l.to_string()).collect()
+use std::fmt::{Display, Formatter};
+
+use crate::count::{BigUint, Count};
+
+/// Readings packed into integers, bit `width - 1` being the first character of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub width: usize,
+    pub readings: Vec<u128>,
 }
 
-#[aoc(day3, part1)]
-pub fn part1(inputs: &[String]) -> i32 {
-    let length = inputs.len() as i32;
-    let gamma_bin: String = inputs.iter()
-        // map each line to a Vec<i32> with 1/0
-        .map(|l| l.chars().map(|c| c.to_digit(10).unwrap() as i32).collect::<Vec<i32>>())
-        // fold vectors by summing each char position
-        .into_iter().fold(vec![0i32; 12], |sum, l| -> Vec<i32> {
-            sum.iter().zip(l).map(|(s, e)| s + e).collect()
-        })
-        // map to binary
-        .iter().map(|e| if e >= &(length / 2) { '1' } else { '0' }).collect();
-
-    let gamma = i32::from_str_radix(gamma_bin.as_str(), 2).unwrap();
-    let epsilon = ((2_i32.pow(gamma_bin.len() as u32)) - 1) - gamma;
-    gamma * epsilon
-}
-
-pub fn filter_by_compare(inputs: &[String], compare: char) -> i32 {
-    let mut candidates = inputs.clone().to_vec();
-    let inverse = match compare {
-        '1' => '0',
-        _ => '1'
-    };
-
-    for i in 0..inputs[0].len() {
-        let ones: i32 = candidates.clone().iter().map(|l| if l.chars().nth(i).unwrap() == '1' { 1 } else { 0 }).sum();
-        let compare = match ones.cmp(&(candidates.len() as i32 - ones)) {
-            std::cmp::Ordering::Less => compare,
-            _ => inverse
-        };
-        candidates = candidates.into_iter().filter(|l| l.chars().nth(i).unwrap() == compare).collect::<Vec<_>>();
-        if candidates.len() == 1 {
-            return i32::from_str_radix(candidates[0].clone().as_str(), 2).unwrap();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportError {
+    Empty,
+    /// lines are 1 based
+    TooWide { line: usize, width: usize },
+    WidthMismatch { line: usize, expected: usize, found: usize },
+    InvalidDigit { line: usize, digit: char },
+}
+
+impl Display for ReportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportError::Empty => write!(f, "empty report"),
+            ReportError::TooWide { line, width } => write!(f, "line {}: {} bits, at most {} are supported", line, width, Report::MAX_WIDTH),
+            ReportError::WidthMismatch { line, expected, found } => write!(f, "line {}: expected {} bits, found {}", line, expected, found),
+            ReportError::InvalidDigit { line, digit } => write!(f, "line {}: invalid digit {:?}", line, digit),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+/// Which value the bit criteria keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    /// oxygen generator rating
+    MostCommon,
+    /// CO2 scrubber rating
+    LeastCommon,
+}
+
+/// What to keep when a bit is 0 and 1 equally often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    Ones,
+    Zeros,
+    /// leave the candidates untouched for this bit
+    KeepAll,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitCriteria {
+    pub criterion: Criterion,
+    pub tie: TieBreak,
+}
+
+impl BitCriteria {
+    pub fn oxygen() -> Self {
+        BitCriteria { criterion: Criterion::MostCommon, tie: TieBreak::Ones }
+    }
+
+    pub fn co2() -> Self {
+        BitCriteria { criterion: Criterion::LeastCommon, tie: TieBreak::Zeros }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rating {
+    pub value: u128,
+    /// candidates left after each filtered bit, first bit first
+    pub trace: Vec<usize>,
+}
+
+/// Positions count from the first character of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RatingError {
+    NoCandidates { position: usize, trace: Vec<usize> },
+    /// all bits were used up with several readings left, e.g. duplicates
+    Ambiguous { candidates: Vec<u128>, trace: Vec<usize> },
+    Tie { position: usize, trace: Vec<usize> },
+}
+
+impl Display for RatingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RatingError::NoCandidates { position, .. } => write!(f, "no candidates left after bit {}", position),
+            RatingError::Ambiguous { candidates, .. } => write!(f, "{} candidates left after the last bit", candidates.len()),
+            RatingError::Tie { position, .. } => write!(f, "tie at bit {}", position),
         }
     }
-    0
+}
+
+impl std::error::Error for RatingError {}
+
+impl Report {
+    pub const MAX_WIDTH: usize = 128;
+
+    pub fn parse(input: &str) -> Result<Report, ReportError> {
+        let mut width = None;
+        let readings = input.lines().enumerate().filter(|(_, l)| !l.is_empty()).map(|(i, l)| {
+            let line = i + 1;
+            let found = l.len();
+            match width {
+                _ if found > Report::MAX_WIDTH => return Err(ReportError::TooWide { line, width: found }),
+                Some(expected) if expected != found => return Err(ReportError::WidthMismatch { line, expected, found }),
+                _ => width = Some(found),
+            }
+            l.chars().try_fold(0u128, |acc, c| match c {
+                '0' | '1' => Ok(acc << 1 | (c == '1') as u128),
+                _ => Err(ReportError::InvalidDigit { line, digit: c }),
+            })
+        }).collect::<Result<Vec<_>, _>>()?;
+        match width {
+            Some(width) => Ok(Report { width, readings }),
+            None => Err(ReportError::Empty),
+        }
+    }
+
+    fn mask(&self) -> u128 {
+        u128::MAX >> (Report::MAX_WIDTH - self.width)
+    }
+
+    /// Most common value of every bit, ties counting as 1.
+    pub fn gamma(&self) -> u128 {
+        (0..self.width)
+            .filter(|&bit| 2 * ones(&self.readings, bit) >= self.readings.len())
+            .fold(0, |acc, bit| acc | 1 << bit)
+    }
+
+    pub fn epsilon(&self) -> u128 {
+        !self.gamma() & self.mask()
+    }
+
+    /// Filter the readings from the first bit on until only one remains.
+    pub fn rating(&self, criteria: BitCriteria) -> Result<Rating, RatingError> {
+        let mut candidates = self.readings.clone();
+        let mut trace = Vec::new();
+        for bit in (0..self.width).rev() {
+            if candidates.len() == 1 {
+                break;
+            }
+            let position = self.width - 1 - bit;
+            let ones = ones(&candidates, bit);
+            let zeros = candidates.len() - ones;
+            let keep = match (ones.cmp(&zeros), criteria.tie) {
+                (std::cmp::Ordering::Equal, TieBreak::Ones) => Some(1),
+                (std::cmp::Ordering::Equal, TieBreak::Zeros) => Some(0),
+                (std::cmp::Ordering::Equal, TieBreak::KeepAll) => None,
+                (std::cmp::Ordering::Equal, TieBreak::Fail) => return Err(RatingError::Tie { position, trace }),
+                (order, _) => Some(((order == std::cmp::Ordering::Greater) == (criteria.criterion == Criterion::MostCommon)) as u128),
+            };
+            if let Some(keep) = keep {
+                candidates.retain(|r| r >> bit & 1 == keep);
+            }
+            trace.push(candidates.len());
+            if candidates.is_empty() {
+                return Err(RatingError::NoCandidates { position, trace });
+            }
+        }
+        match candidates[..] {
+            [value] => Ok(Rating { value, trace }),
+            _ => Err(RatingError::Ambiguous { candidates, trace }),
+        }
+    }
+}
+
+fn ones(readings: &[u128], bit: usize) -> usize {
+    readings.iter().filter(|&&r| r >> bit & 1 == 1).count()
+}
+
+/// Binary trie of the readings, first bit at the root, with the number of readings below every node.
+pub struct Trie {
+    width: usize,
+    // node 0 is the root, so a child index of 0 means no child
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Default)]
+struct TrieNode {
+    count: usize,
+    children: [usize; 2],
+}
+
+impl Trie {
+    pub fn new(report: &Report) -> Trie {
+        let mut nodes = vec![TrieNode::default()];
+        for &r in &report.readings {
+            let mut node = 0;
+            nodes[node].count += 1;
+            for bit in (0..report.width).rev() {
+                let b = (r >> bit & 1) as usize;
+                if nodes[node].children[b] == 0 {
+                    nodes[node].children[b] = nodes.len();
+                    nodes.push(TrieNode::default());
+                }
+                node = nodes[node].children[b];
+                nodes[node].count += 1;
+            }
+        }
+        Trie { width: report.width, nodes }
+    }
+
+    fn count(&self, node: usize, b: usize) -> usize {
+        match self.nodes[node].children[b] {
+            0 => 0,
+            child => self.nodes[child].count,
+        }
+    }
+
+    /// Same as [Report::rating] with the puzzle's tie rules, `None` without a unique candidate.
+    pub fn rating(&self, criterion: Criterion) -> Option<u128> {
+        let mut node = 0;
+        let mut value = 0;
+        for _ in 0..self.width {
+            let (zeros, ones) = (self.count(node, 0), self.count(node, 1));
+            let b = match criterion {
+                // a single candidate left just follows its path
+                _ if self.nodes[node].count == 1 => (ones == 1) as usize,
+                Criterion::MostCommon => (ones >= zeros) as usize,
+                Criterion::LeastCommon => (ones < zeros) as usize,
+            };
+            node = self.nodes[node].children[b];
+            if node == 0 {
+                return None;
+            }
+            value = value << 1 | b as u128;
+        }
+        (self.nodes[node].count == 1).then_some(value)
+    }
+}
+
+#[aoc_generator(day3)]
+pub fn generator(input: &str) -> Report {
+    Report::parse(input).unwrap()
+}
+
+/// Product of two readings, which needs up to 256 bits for the widest reports.
+fn product(a: u128, b: u128) -> BigUint {
+    BigUint::from(a).checked_mul(&BigUint::from(b)).unwrap()
+}
+
+#[aoc(day3, part1)]
+pub fn part1(report: &Report) -> BigUint {
+    product(report.gamma(), report.epsilon())
 }
 
 #[aoc(day3, part2)]
-pub fn part2(inputs: &[String]) -> i32 {
-    filter_by_compare(inputs.clone(), '0') * filter_by_compare(inputs.clone(), '1')
+pub fn part2(report: &Report) -> BigUint {
+    let oxygen = report.rating(BitCriteria::oxygen()).unwrap().value;
+    let co2 = report.rating(BitCriteria::co2()).unwrap().value;
+    product(oxygen, co2)
+}
+
+#[aoc(day3, part2, trie)]
+pub fn part2_trie(report: &Report) -> BigUint {
+    let trie = Trie::new(report);
+    let oxygen = trie.rating(Criterion::MostCommon).unwrap();
+    let co2 = trie.rating(Criterion::LeastCommon).unwrap();
+    product(oxygen, co2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    pub fn test1() {
-        //assert_eq!(part1(&SAMPLE), 1721 * 299);
-        assert_eq!(part1(&generator("00100
+    const SAMPLE: &str = "00100
 11110
 10110
 10111
@@ -65,22 +276,79 @@ mod tests {
 10000
 11001
 00010
-01010")), 198)
+01010";
+
+    #[test]
+    pub fn test1() {
+        //assert_eq!(part1(&SAMPLE), 1721 * 299);
+        assert_eq!(part1(&generator(SAMPLE)), BigUint::from(198u64))
     }
 
     #[test]
     pub fn test2() {
-        assert_eq!(part2(&generator("00100
-11110
-10110
-10111
-10101
-01111
-00111
-11100
-10000
-11001
-00010
-01010")), 230)
+        assert_eq!(part2(&generator(SAMPLE)), BigUint::from(230u64))
+    }
+
+    #[test]
+    pub fn test2_trie() {
+        assert_eq!(part2_trie(&generator(SAMPLE)), BigUint::from(230u64))
+    }
+
+    #[test]
+    pub fn test_wide() {
+        // 100 bit readings, the sample shifted into the top bits
+        let wide = generator(&SAMPLE.lines().map(|l| format!("{}{}", l, "0".repeat(95))).collect::<Vec<_>>().join("\n"));
+        assert_eq!(wide.width, 100);
+        assert_eq!(wide.gamma(), 22 << 95);
+        assert_eq!(wide.epsilon(), (9 << 95) | ((1 << 95) - 1));
+        assert_eq!(wide.rating(BitCriteria::oxygen()).unwrap().value, 23 << 95);
+        assert_eq!(wide.rating(BitCriteria::co2()).unwrap().value, 10 << 95);
+        assert_eq!(Trie::new(&wide).rating(Criterion::LeastCommon), Some(10 << 95));
+        assert_eq!(part1(&wide), product(22 << 95, (9 << 95) | ((1 << 95) - 1)));
+        assert_eq!(part2(&wide), BigUint::from(230u64).checked_mul(&BigUint::from(1u128 << 95)).unwrap().checked_mul(&BigUint::from(1u128 << 95)).unwrap());
+        assert_eq!(part2_trie(&wide), part2(&wide));
+    }
+
+    #[test]
+    pub fn test_validate() {
+        assert_eq!(Report::parse(""), Err(ReportError::Empty));
+        assert_eq!(Report::parse("0101\n011\n"), Err(ReportError::WidthMismatch { line: 2, expected: 4, found: 3 }));
+        assert_eq!(Report::parse("0121"), Err(ReportError::InvalidDigit { line: 1, digit: '2' }));
+        assert_eq!(Report::parse(&"1".repeat(129)), Err(ReportError::TooWide { line: 1, width: 129 }));
+        assert_eq!(Report::parse(&"1".repeat(128)).unwrap().readings, vec![u128::MAX]);
+    }
+
+    #[test]
+    pub fn test_rating() {
+        let report = generator(SAMPLE);
+        assert_eq!(report.rating(BitCriteria::oxygen()), Ok(Rating { value: 23, trace: vec![7, 4, 3, 2, 1] }));
+        assert_eq!(report.rating(BitCriteria::co2()), Ok(Rating { value: 10, trace: vec![5, 2, 1] }));
+
+        // the oxygen rating ties on its last bit
+        let fail = BitCriteria { tie: TieBreak::Fail, ..BitCriteria::oxygen() };
+        assert_eq!(report.rating(fail), Err(RatingError::Tie { position: 4, trace: vec![7, 4, 3, 2] }));
+        let zeros = BitCriteria { tie: TieBreak::Zeros, ..BitCriteria::oxygen() };
+        assert_eq!(report.rating(zeros).unwrap().value, 22);
+        let keep = BitCriteria { tie: TieBreak::KeepAll, ..BitCriteria::oxygen() };
+        assert_eq!(report.rating(keep), Err(RatingError::Ambiguous { candidates: vec![22, 23], trace: vec![7, 4, 3, 2, 2] }));
+
+        let duplicates = generator("101\n101\n010");
+        assert_eq!(duplicates.rating(BitCriteria::oxygen()), Err(RatingError::Ambiguous { candidates: vec![5, 5], trace: vec![2, 2, 2] }));
+        assert_eq!(duplicates.rating(BitCriteria::co2()).unwrap().value, 2);
+        let same = generator("11\n11\n01");
+        assert_eq!(same.rating(BitCriteria::co2()), Ok(Rating { value: 1, trace: vec![1] }));
+        assert_eq!(generator("11\n11").rating(BitCriteria::co2()), Err(RatingError::NoCandidates { position: 0, trace: vec![0] }));
+    }
+
+    #[test]
+    pub fn test_trie() {
+        let reports = ["101\n101\n010", "11\n11\n01", "11\n11", "1", "0110\n0111\n1000\n0001\n0110"];
+        for input in reports {
+            let report = generator(input);
+            let trie = Trie::new(&report);
+            for (criterion, criteria) in [(Criterion::MostCommon, BitCriteria::oxygen()), (Criterion::LeastCommon, BitCriteria::co2())] {
+                assert_eq!(trie.rating(criterion), report.rating(criteria).ok().map(|r| r.value), "{}", input);
+            }
+        }
     }
 }
@@ -1,80 +1,284 @@
-use nalgebra::Matrix5;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use nalgebra::DMatrix;
 
-pub type Board = Matrix5<(u32, bool)>;
+pub type Board = DMatrix<u32>;
+
+/// A set of cells that wins once all of them are marked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Row,
+    Column,
+    /// either diagonal, square boards only
+    Diagonal,
+    FullCard,
+    FourCorners,
+}
 
 pub struct Input {
     numbers: Vec<u32>,
     boards: Vec<Board>,
+    patterns: Vec<Pattern>,
+}
+
+/// Boards count from 0 and their rows from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BingoError {
+    InvalidNumber(String),
+    NoBoards,
+    NotRectangular { board: usize, row: usize, expected: usize, found: usize },
+    DuplicateNumber { board: usize, number: u32 },
+    NotSquare { board: usize },
+}
+
+impl Display for BingoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BingoError::InvalidNumber(n) => write!(f, "invalid number {}", n),
+            BingoError::NoBoards => write!(f, "no boards"),
+            BingoError::NotRectangular { board, row, expected, found } => write!(f, "board {} row {}: expected {} numbers, found {}", board, row, expected, found),
+            BingoError::DuplicateNumber { board, number } => write!(f, "board {}: {} appears more than once", board, number),
+            BingoError::NotSquare { board } => write!(f, "board {}: diagonals need a square board", board),
+        }
+    }
+}
+
+impl std::error::Error for BingoError {}
+
+fn number(s: &str) -> Result<u32, BingoError> {
+    s.parse::<u32>().map_err(|_| BingoError::InvalidNumber(s.to_string()))
+}
+
+fn parse_board(i: usize, b: &str) -> Result<Board, BingoError> {
+    let rows = b.lines().filter(|l| !l.trim().is_empty()).map(|l| l.split_whitespace().map(number).collect::<Result<Vec<_>, _>>()).collect::<Result<Vec<_>, _>>()?;
+    let width = rows[0].len();
+    if let Some((row, r)) = rows.iter().enumerate().find(|(_, r)| r.len() != width) {
+        return Err(BingoError::NotRectangular { board: i, row: row + 1, expected: width, found: r.len() });
+    }
+    let mut seen = HashSet::new();
+    if let Some(&number) = rows.iter().flatten().find(|&&n| !seen.insert(n)) {
+        return Err(BingoError::DuplicateNumber { board: i, number });
+    }
+    let cells = rows.iter().flatten().copied().collect::<Vec<_>>();
+    Ok(Board::from_row_slice(rows.len(), width, &cells))
+}
+
+impl Input {
+    /// Board dimensions come from the input, the patterns default to rows and columns.
+    pub fn parse(input: &str) -> Result<Input, BingoError> {
+        let mut lines = input.split("\n\n");
+        let numbers = lines.next().unwrap_or_default().trim().split(',').map(number).collect::<Result<Vec<_>, _>>()?;
+        let boards = lines
+            .filter(|b| !b.trim().is_empty())
+            .enumerate()
+            .map(|(i, b)| parse_board(i, b))
+            .collect::<Result<Vec<_>, _>>()?;
+        if boards.is_empty() {
+            return Err(BingoError::NoBoards);
+        }
+        Ok(Input { numbers, boards, patterns: vec![Pattern::Row, Pattern::Column] })
+    }
+
+    pub fn with_patterns(mut self, patterns: &[Pattern]) -> Result<Input, BingoError> {
+        if patterns.contains(&Pattern::Diagonal) {
+            if let Some(board) = self.boards.iter().position(|b| !b.is_square()) {
+                return Err(BingoError::NotSquare { board });
+            }
+        }
+        self.patterns = patterns.to_vec();
+        Ok(self)
+    }
+
+    /// Replace the draw order, e.g. with one from [winning_order].
+    pub fn with_numbers(mut self, numbers: Vec<u32>) -> Input {
+        self.numbers = numbers;
+        self
+    }
+
+    pub fn numbers(&self) -> &[u32] {
+        &self.numbers
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+}
+
+/// Puzzle input format, numbers right aligned in the board columns.
+impl Display for Input {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(","))?;
+        let width = self.boards.iter().flat_map(|b| b.iter()).map(|n| n.to_string().len()).max().unwrap_or(1);
+        for board in &self.boards {
+            writeln!(f)?;
+            for row in board.row_iter() {
+                writeln!(f, "{}", row.iter().map(|n| format!("{:>w$}", n, w = width)).collect::<Vec<_>>().join(" "))?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[aoc_generator(day4)]
 pub fn generator(input: &str) -> Input {
-    let mut lines = input.split("\n\n");
-
-    let numbers = lines.next().unwrap().split(',').map(|x| x.parse::<u32>().unwrap()).collect();
+    Input::parse(input).unwrap()
+}
 
-    let boards = lines
-        .map(|b| {
-            Matrix5::from_iterator(b.lines().flat_map(|l| {
-                l.split_whitespace().map(|x| (x.parse::<u32>().unwrap(), false))
-            }))
-        }).collect::<Vec<Board>>();
-    Input {numbers, boards}
+/// Cells of every winning line the patterns give on a board of this shape.
+pub fn lines((rows, cols): (usize, usize), patterns: &[Pattern]) -> Vec<Vec<(usize, usize)>> {
+    patterns.iter().flat_map(|p| match p {
+        Pattern::Row => (0..rows).map(|r| (0..cols).map(|c| (r, c)).collect()).collect(),
+        Pattern::Column => (0..cols).map(|c| (0..rows).map(|r| (r, c)).collect()).collect(),
+        Pattern::Diagonal => vec![(0..rows).map(|i| (i, i)).collect(), (0..rows).map(|i| (i, cols - 1 - i)).collect()],
+        Pattern::FullCard => vec![(0..rows).flat_map(|r| (0..cols).map(move |c| (r, c))).collect()],
+        Pattern::FourCorners => {
+            let mut corners = vec![(0, 0), (0, cols - 1), (rows - 1, 0), (rows - 1, cols - 1)];
+            corners.dedup();
+            vec![corners]
+        }
+    }).collect()
 }
 
-fn update_played(board: &mut Board, num: u32) {
-    board.iter_mut().for_each(|x| if x.0 == num {x.1 = true;})
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Win {
+    pub board: usize,
+    /// 0 based position in the draw order
+    pub draw: usize,
+    pub number: u32,
+    pub score: u32,
 }
 
-fn check_win(board: &Board) -> bool {
-    board.column_iter().any(|c| c.iter().all(|(_, played)| *played)) ||
-        board.row_iter().any(|r| r.iter().all(|(_, played)| *played))
+struct Progress {
+    /// per cell the lines it belongs to
+    cell_lines: Vec<Vec<usize>>,
+    /// cells still missing per line
+    missing: Vec<usize>,
+    marked: Vec<bool>,
+    unmarked_sum: u32,
+    won: bool,
 }
 
-fn sum_board_unmarked(board: &Board) -> u32 {
-    board.iter().filter_map(|(x, played)| if ! *played {Some(*x)} else {None}).sum()
+impl Progress {
+    fn new(board: &Board, patterns: &[Pattern]) -> Self {
+        let lines = lines(board.shape(), patterns);
+        let mut cell_lines = vec![Vec::new(); board.len()];
+        for (i, line) in lines.iter().enumerate() {
+            for &(r, c) in line {
+                cell_lines[board.shape().1 * r + c].push(i);
+            }
+        }
+        Progress {
+            cell_lines,
+            missing: lines.iter().map(|l| l.len()).collect(),
+            marked: vec![false; board.len()],
+            unmarked_sum: board.sum(),
+            won: false,
+        }
+    }
+
+    /// Mark a cell, returning whether that completed a line.
+    fn mark(&mut self, cell: usize, num: u32) -> bool {
+        if std::mem::replace(&mut self.marked[cell], true) {
+            return false;
+        }
+        self.unmarked_sum -= num;
+        let missing = &mut self.missing;
+        self.cell_lines[cell].iter().fold(false, |done, &l| {
+            missing[l] -= 1;
+            done || missing[l] == 0
+        })
+    }
 }
 
-#[aoc(day4, part1)]
-pub fn part1(inputs: &Input) -> u32 {
-    let (numbers, mut boards) = (inputs.numbers.clone(), inputs.boards.clone());
-    for num in numbers {
-        for b in boards.iter_mut() {
-            update_played(b, num);
-            if check_win(b) {
-                return num * sum_board_unmarked(b)
+/// Play the whole draw, returning the boards in the order they won.
+pub fn simulate(inputs: &Input) -> Vec<Win> {
+    // number -> (board, row major cell), boards in order so ties go to the earlier board
+    let mut index: HashMap<u32, Vec<(usize, usize)>> = HashMap::new();
+    for (b, board) in inputs.boards.iter().enumerate() {
+        let (rows, cols) = board.shape();
+        for (r, c) in (0..rows).flat_map(|r| (0..cols).map(move |c| (r, c))) {
+            index.entry(board[(r, c)]).or_default().push((b, cols * r + c));
+        }
+    }
+
+    let mut progress = inputs.boards.iter().map(|b| Progress::new(b, &inputs.patterns)).collect::<Vec<_>>();
+    let mut wins = Vec::new();
+    for (draw, &number) in inputs.numbers.iter().enumerate() {
+        for &(board, cell) in index.get(&number).into_iter().flatten() {
+            let p = &mut progress[board];
+            if p.won || !p.mark(cell, number) {
+                continue;
             }
+            p.won = true;
+            wins.push(Win { board, draw, number, score: number * p.unmarked_sum });
+        }
+        if wins.len() == inputs.boards.len() {
+            break;
         }
     }
-    0
+    wins
+}
+
+fn line_numbers(board: &Board, patterns: &[Pattern]) -> Vec<HashSet<u32>> {
+    let mut lines = lines(board.shape(), patterns).iter().map(|l| l.iter().map(|&cell| board[cell]).collect::<HashSet<_>>()).collect::<Vec<_>>();
+    lines.sort_by_key(|l| l.len());
+    lines
+}
+
+/// Earliest 0 based draw position at which each board could win if the numbers were drawn in any order,
+/// `None` when none of its lines can be completed from the drawn numbers.
+pub fn earliest_wins(inputs: &Input) -> Vec<Option<usize>> {
+    let pool = inputs.numbers.iter().copied().collect::<HashSet<_>>();
+    inputs.boards.iter().map(|b| {
+        line_numbers(b, &inputs.patterns).iter().filter(|l| l.is_subset(&pool)).map(|l| l.len() - 1).min()
+    }).collect()
+}
+
+/// Reorder the drawn numbers so that `board` wins before every other board, as early as possible.
+/// `None` if no order does, e.g. when an earlier board has the same lines.
+pub fn winning_order(inputs: &Input, board: usize) -> Option<Vec<u32>> {
+    let pool = inputs.numbers.iter().copied().collect::<HashSet<_>>();
+    let others = inputs.boards.iter().enumerate()
+        .filter(|&(o, _)| o != board)
+        .flat_map(|(o, b)| line_numbers(b, &inputs.patterns).into_iter().map(move |l| (o, l)))
+        .collect::<Vec<_>>();
+    // drawing a line with `last` as the final number, every other line completed on the way has to end
+    // with that same number and lose the tie by belonging to a later board
+    let (line, last) = line_numbers(&inputs.boards[board], &inputs.patterns).into_iter()
+        .filter(|l| l.is_subset(&pool))
+        .find_map(|l| {
+            let mut candidates = l.iter().copied().collect::<Vec<_>>();
+            candidates.sort_unstable();
+            let last = candidates.into_iter().find(|x| {
+                others.iter().filter(|(_, m)| m.is_subset(&l)).all(|(o, m)| m.contains(x) && *o > board)
+            })?;
+            Some((l, last))
+        })?;
+
+    let (before, after) = inputs.numbers.iter().copied().partition::<Vec<_>, _>(|n| line.contains(n) && *n != last);
+    let (last, after) = after.into_iter().partition::<Vec<_>, _>(|&n| n == last);
+    Some([before, last, after].concat())
+}
+
+#[aoc(day4, part1)]
+pub fn part1(inputs: &Input) -> u32 {
+    simulate(inputs).first().map_or(0, |w| w.score)
 }
 
 #[aoc(day4, part2)]
 pub fn part2(inputs: &Input) -> u32 {
-    let (numbers, mut boards) = (inputs.numbers.clone(), inputs.boards.clone());
-    let mut won_boards = vec![false; boards.len()];
-
-    for num in numbers {
-        for (i, b) in boards.iter_mut().enumerate(){
-            update_played(b, num);
-            if check_win(b) {
-                won_boards[i] = true;
-                if won_boards.iter().all(|x| *x) {
-                    return num * sum_board_unmarked(&b)
-                }
-            }
-        }
+    let wins = simulate(inputs);
+    match wins.last() {
+        Some(w) if wins.len() == inputs.boards.len() => w.score,
+        _ => 0,
     }
-    0
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    pub fn test1() {
-        let input = generator("7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+    const SAMPLE: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
 
 22 13 17 11  0
  8  2 23  4 24
@@ -92,31 +296,96 @@ mod tests {
 10 16 15  9 19
 18  8 23 26 20
 22 11 13  6  5
- 2  0 12  3  7");
+ 2  0 12  3  7";
+
+    #[test]
+    pub fn test1() {
+        let input = generator(SAMPLE);
         assert_eq!(part1(&input), 4512)
     }
 
     #[test]
     pub fn test2() {
-        let input = generator("7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+        let input = generator(SAMPLE);
+        assert_eq!(part2(&input), 1924)
+    }
 
-22 13 17 11  0
- 8  2 23  4 24
-21  9 14 16  7
- 6 10  3 18  5
- 1 12 20 15 19
+    #[test]
+    pub fn test_simulate() {
+        let wins = simulate(&generator(SAMPLE));
+        assert_eq!(wins, vec![
+            Win { board: 2, draw: 11, number: 24, score: 4512 },
+            Win { board: 0, draw: 13, number: 16, score: 2192 },
+            Win { board: 1, draw: 14, number: 13, score: 1924 },
+        ]);
+    }
 
- 3 15  0  2 22
- 9 18 13 17  5
-19  8  7 25 23
-20 11 10 24  4
-14 21 16 12  6
+    #[test]
+    pub fn test_patterns() {
+        let input = generator("9,7,5,3,2,4\n\n1 2 3\n4 5 6\n7 8 9\n\n9 8 3\n6 5 4\n1 2 7");
+        // the second board completes the diagonal 9, 5, 7 and the first its corners 1, 3, 9 and 7
+        assert_eq!(part1(&input.with_patterns(&[Pattern::Diagonal]).unwrap()), 5 * (8 + 3 + 6 + 4 + 1 + 2));
+        let input = generator("1,9,7,3,2,4\n\n1 2 3\n4 5 6\n7 8 9\n\n9 8 3\n6 5 4\n1 2 7");
+        assert_eq!(part1(&input.with_patterns(&[Pattern::FourCorners]).unwrap()), 3 * (2 + 4 + 5 + 6 + 8));
+        let input = generator("1,2,3,4,5,6\n\n1 2 3\n4 5 6\n\n6 5\n4 3\n2 1");
+        assert_eq!(part1(&input), 2 * (6 + 5 + 4 + 3));
+        assert_eq!(part1(&input.with_patterns(&[Pattern::FullCard]).unwrap()), 0);
+        assert_eq!(part1(&generator("4,5,6\n\n1 2 3\n4 5 6\n\n6 5\n4 3\n2 1")), 6 * 6);
+    }
 
-14 21 17 24  4
-10 16 15  9 19
-18  8 23 26 20
-22 11 13  6  5
- 2  0 12  3  7");
-        assert_eq!(part2(&input), 1924)
+    #[test]
+    pub fn test_validate() {
+        assert_eq!(Input::parse("1,2\n\n1 2\n3").err(), Some(BingoError::NotRectangular { board: 0, row: 2, expected: 2, found: 1 }));
+        assert_eq!(Input::parse("1,2\n\n1 2\n3 4\n\n1 2\n2 4").err(), Some(BingoError::DuplicateNumber { board: 1, number: 2 }));
+        assert_eq!(Input::parse("1,x\n\n1 2").err(), Some(BingoError::InvalidNumber("x".to_string())));
+        assert_eq!(Input::parse("1,2").err(), Some(BingoError::NoBoards));
+        let input = Input::parse("1,2\n\n1 2 3\n4 5 6").unwrap();
+        assert_eq!(input.boards[0].shape(), (2, 3));
+        assert_eq!(input.with_patterns(&[Pattern::Row, Pattern::Diagonal]).err(), Some(BingoError::NotSquare { board: 0 }));
+    }
+
+    #[test]
+    pub fn test_blank_lines() {
+        let spaced = SAMPLE.replace("\n\n", "\n\n\n").replace("\n\n\n", "\n\n \n\n");
+        let input = Input::parse(&spaced).unwrap();
+        assert_eq!(input.boards, generator(SAMPLE).boards);
+        assert_eq!(part1(&input), 4512);
+        assert_eq!(crate::detect::detect(&spaced).days(), vec![4]);
+    }
+
+    #[test]
+    pub fn test_earliest() {
+        let input = generator(SAMPLE);
+        assert_eq!(input.to_string().trim_end(), SAMPLE.trim_end());
+        assert_eq!(earliest_wins(&input), vec![Some(4); 3]);
+        for board in 0..3 {
+            let numbers = winning_order(&input, board).unwrap();
+            assert_eq!(numbers.len(), input.numbers.len());
+            let rigged = generator(SAMPLE).with_numbers(numbers);
+            let first = simulate(&rigged)[0];
+            assert_eq!((first.board, first.draw), (board, 4));
+            // servers get the rigged game as puzzle text
+            let replayed = generator(&rigged.to_string());
+            assert_eq!(replayed.numbers(), rigged.numbers());
+            assert_eq!(replayed.boards(), input.boards());
+            assert_eq!(simulate(&replayed)[0], first);
+        }
+
+        // the second board only wins together with the first one, which takes the tie
+        let input = generator("1,2,3,4,5\n\n1 2\n3 4\n\n2 1\n4 3\n\n7 8\n9 10");
+        assert_eq!(earliest_wins(&input), vec![Some(1), Some(1), None]);
+        assert_eq!(winning_order(&input, 1), None);
+        assert_eq!(winning_order(&input, 2), None);
+        assert_eq!(winning_order(&input, 0), Some(vec![2, 1, 3, 4, 5]));
+
+        // the row 1 2 3 has to end with 2 or 3 so the line 2 3 of the later board completes on the same draw
+        let input = generator("1,2,3,4,5,6,7,8,9\n\n1 2 3\n4 5 6\n7 8 9\n\n2 3\n8 5");
+        let numbers = winning_order(&input, 0).unwrap();
+        assert_eq!(numbers[..3], [1, 3, 2]);
+        let rigged = input.with_numbers(numbers);
+        assert_eq!(simulate(&rigged)[..2], [
+            Win { board: 0, draw: 2, number: 2, score: 2 * (4 + 5 + 6 + 7 + 8 + 9) },
+            Win { board: 1, draw: 2, number: 2, score: 2 * (8 + 5) },
+        ]);
     }
 }
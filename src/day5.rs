@@ -1,10 +1,166 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use itertools::Itertools;
 
 
 pub type Point = (i32, i32);
 pub type Line = (Point, Point);
 
+/// How a vent line runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// single points count as horizontal
+    Horizontal,
+    Vertical,
+    Diagonal,
+    Sloped,
+}
+
+/// How lines are turned into points, both agree on all but sloped lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Raster {
+    /// exactly the lattice points on the line
+    Lattice,
+    /// one point per step along the major axis, closest to the line
+    Bresenham,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vent {
+    pub line: Line,
+    pub kind: Kind,
+    /// the mode used for the points of this line, always `Lattice` unless it is sloped
+    pub raster: Raster,
+}
+
+impl Vent {
+    pub fn points(&self) -> Vec<Point> {
+        points(&self.line, self.raster)
+    }
+}
+
+pub fn classify(&((x1, y1), (x2, y2)): &Line) -> Kind {
+    match (x2 - x1, y2 - y1) {
+        (_, 0) => Kind::Horizontal,
+        (0, _) => Kind::Vertical,
+        (dx, dy) if dx.abs() == dy.abs() => Kind::Diagonal,
+        _ => Kind::Sloped,
+    }
+}
+
+pub fn points(&((x1, y1), (x2, y2)): &Line, raster: Raster) -> Vec<Point> {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    match raster {
+        Raster::Lattice => {
+            let g = gcd(dx.abs() as i64, dy.abs() as i64) as i32;
+            let (sx, sy) = (dx / g.max(1), dy / g.max(1));
+            (0..=g).map(|d| (x1 + sx * d, y1 + sy * d)).collect()
+        }
+        Raster::Bresenham => {
+            let (sx, sy) = (dx.signum(), dy.signum());
+            let (dx, dy) = (dx.abs(), -dy.abs());
+            let (mut x, mut y, mut err) = (x1, y1, dx + dy);
+            let mut points = vec![(x, y)];
+            while (x, y) != (x2, y2) {
+                let e2 = 2 * err;
+                if e2 >= dy {
+                    err += dy;
+                    x += sx;
+                }
+                if e2 <= dx {
+                    err += dx;
+                    y += sy;
+                }
+                points.push((x, y));
+            }
+            points
+        }
+    }
+}
+
+/// Parse and classify the lines, sloped ones get rasterised with `raster`.
+pub fn vents(input: &str, raster: Raster) -> Vec<Vent> {
+    generator(input).into_iter().map(|line| {
+        let kind = classify(&line);
+        Vent { line, kind, raster: if kind == Kind::Sloped { raster } else { Raster::Lattice } }
+    }).collect()
+}
+
+/// How many lines cover each point, only points covered at least once are stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    counts: HashMap<Point, u32>,
+}
+
+impl Field {
+    pub fn new(inputs: &[Line], raster: Raster) -> Field {
+        let mut counts = HashMap::new();
+        for p in inputs.iter().flat_map(|l| points(l, raster)) {
+            *counts.entry(p).or_insert(0) += 1;
+        }
+        Field { counts }
+    }
+
+    pub fn at(&self, p: Point) -> u32 {
+        self.counts.get(&p).copied().unwrap_or(0)
+    }
+
+    /// Number of points covered by at least `k` lines.
+    pub fn at_least(&self, k: u32) -> usize {
+        self.counts.values().filter(|&&v| v >= k).count()
+    }
+
+    /// The `n` most covered points, ties in (x, y) order.
+    pub fn hottest(&self, n: usize) -> Vec<(Point, u32)> {
+        let mut points = self.counts.iter().map(|(&p, &v)| (p, v)).collect::<Vec<_>>();
+        points.sort_unstable_by_key(|&(p, v)| (std::cmp::Reverse(v), p));
+        points.truncate(n);
+        points
+    }
+
+    /// Sum of the counts in the rectangle spanned by two corners, both inclusive.
+    pub fn total(&self, (x1, y1): Point, (x2, y2): Point) -> u64 {
+        let (xs, ys) = (x1.min(x2)..=x1.max(x2), y1.min(y2)..=y1.max(y2));
+        self.counts.iter().filter(|((x, y), _)| xs.contains(x) && ys.contains(y)).map(|(_, &v)| v as u64).sum()
+    }
+
+    /// Smallest and largest corner of the covered points.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let xs = self.counts.keys().map(|p| p.0).minmax().into_option()?;
+        let ys = self.counts.keys().map(|p| p.1).minmax().into_option()?;
+        Some(((xs.0, ys.0), (xs.1, ys.1)))
+    }
+
+    /// Covered points as `x,y,count` rows, sorted by y and x.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "x,y,count\n".to_string();
+        for ((x, y), v) in self.counts.iter().sorted_by_key(|((x, y), _)| (*y, *x)) {
+            csv += &format!("{},{},{}\n", x, y, v);
+        }
+        csv
+    }
+
+    /// Plain PPM heatmap of the bounds, white where nothing is and full red at the maximum.
+    pub fn to_ppm(&self) -> String {
+        let ((x1, y1), (x2, y2)) = self.bounds().unwrap_or(((0, 0), (0, 0)));
+        let max = self.counts.values().max().copied().unwrap_or(1);
+        let mut ppm = format!("P3\n{} {}\n255\n", x2 - x1 + 1, y2 - y1 + 1);
+        for y in y1..=y2 {
+            let row = (x1..=x2).map(|x| {
+                let fade = 255 - 255 * self.at((x, y)) / max;
+                format!("255 {} {}", fade, fade)
+            }).join(" ");
+            ppm += &row;
+            ppm.push('\n');
+        }
+        ppm
+    }
+}
+
+/// Number of points covered by at least two of the lines.
+pub fn overlaps(inputs: &[Line], raster: Raster) -> usize {
+    Field::new(inputs, raster).at_least(2)
+}
+
 #[aoc_generator(day5)]
 pub fn generator(input: &str) -> Vec<Line> {
     input
@@ -93,11 +249,10 @@ pub fn part2(inputs: &[Line]) -> usize {
     let mut map: HashMap<Point, i32> = HashMap::new();
     for l in inputs {
         let &((x1, y1), (x2, y2)) = l;
-        // no diagonals
+        // diagonals and other slopes
         if ! (x1 == x2 || y1 == y2) {
-            let (xd, yd) = ((x2-x1).signum(), (y2-y1).signum());
-            for d in 0..=((x2-x1).abs()) {
-                *map.entry((x1+(d*xd), y1+(d*yd))).or_insert(0) += 1;
+            for p in points(l, Raster::Lattice) {
+                *map.entry(p).or_insert(0) += 1;
             }
         } else {
             // swap to make ranges work
@@ -124,10 +279,11 @@ pub fn part2_iter(inputs: &[Line]) -> usize {
     inputs
         .iter()
         .fold(HashMap::<Point, i32>::new(), |mut map, &((x1, y1), (x2, y2))| {
-            let (xd, yd) = ((x2-x1).signum(), (y2-y1).signum());
-            // number of step into_iter
-            (0..=((x2-x1).abs().max((y2-y1).abs())))
-                .into_iter()
+            let (dx, dy) = (x2-x1, y2-y1);
+            // number of lattice steps, also for lines that are not 45 degrees
+            let g = gcd(dx.abs() as i64, dy.abs() as i64) as i32;
+            let (xd, yd) = (dx / g.max(1), dy / g.max(1));
+            (0..=g)
                 // create x,y points touched from delta
                 .map(|d| (x1 + (xd * d), (y1 + (yd * d))))
                 // update map
@@ -138,13 +294,131 @@ pub fn part2_iter(inputs: &[Line]) -> usize {
         .filter(|(_, &v)| v >= 2).count()
 }
 
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Direction and offset of the infinite line through a segment.
+type Carrier = ((i64, i64), i64);
+
+/// Lattice points `start + t * dir` for `t` in `0..=steps`, `dir` primitive and pointing right or up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: (i64, i64),
+    dir: (i64, i64),
+    steps: i64,
+}
+
+impl Segment {
+    fn new(&((x1, y1), (x2, y2)): &Line) -> Segment {
+        let (x1, y1, x2, y2) = (x1 as i64, y1 as i64, x2 as i64, y2 as i64);
+        let (start, (dx, dy)) = if (x2, y2) < (x1, y1) { ((x2, y2), (x1 - x2, y1 - y2)) } else { ((x1, y1), (x2 - x1, y2 - y1)) };
+        match gcd(dx.abs(), dy.abs()) {
+            // a single point
+            0 => Segment { start, dir: (1, 0), steps: 0 },
+            g => Segment { start, dir: (dx / g, dy / g), steps: g },
+        }
+    }
+
+    fn end(&self) -> (i64, i64) {
+        (self.start.0 + self.steps * self.dir.0, self.start.1 + self.steps * self.dir.1)
+    }
+
+    /// The line through the segment, and the position of `p` along it.
+    fn carrier(dir: (i64, i64), p: (i64, i64)) -> (Carrier, i64) {
+        let norm = dir.0 * dir.0 + dir.1 * dir.1;
+        ((dir, dir.0 * p.1 - dir.1 * p.0), (dir.0 * p.0 + dir.1 * p.1).div_euclid(norm))
+    }
+
+    /// The lattice point both segments share, if they are not parallel.
+    fn crossing(&self, other: &Segment) -> Option<(i64, i64)> {
+        let ((a, b), (c, d)) = (self.dir, other.dir);
+        let det = c * b - a * d;
+        if det == 0 {
+            return None;
+        }
+        let (dx, dy) = (other.start.0 - self.start.0, other.start.1 - self.start.1);
+        let (t, s) = (c * dy - d * dx, a * dy - b * dx);
+        if t % det != 0 || s % det != 0 {
+            return None;
+        }
+        let (t, s) = (t / det, s / det);
+        ((0..=self.steps).contains(&t) && (0..=other.steps).contains(&s)).then(|| (self.start.0 + t * a, self.start.1 + t * b))
+    }
+}
+
+/// Number of points covered by at least two lines, without visiting every point of the lines.
+///
+/// Lines on the same carrier are merged with a 1D sweep, the crossings of the remaining pairs come from
+/// a sweep over x that only compares lines with overlapping x ranges.
+pub fn overlaps_sweep(inputs: &[Line]) -> usize {
+    let mut segments = inputs.iter().map(Segment::new).collect::<Vec<_>>();
+
+    // ranges of positions covered at least twice, per carrier
+    let mut events: HashMap<Carrier, Vec<(i64, i64)>> = HashMap::new();
+    for s in &segments {
+        let (carrier, from) = Segment::carrier(s.dir, s.start);
+        events.entry(carrier).or_default().extend([(from, 1), (from + s.steps + 1, -1)]);
+    }
+    let doubles = events.into_iter().map(|(carrier, mut events)| {
+        events.sort_unstable();
+        let mut ranges: Vec<(i64, i64)> = Vec::new();
+        let mut cover = 0;
+        for (pos, delta) in events {
+            let before = cover;
+            cover += delta;
+            match (before >= 2, cover >= 2) {
+                (false, true) => ranges.push((pos, pos)),
+                (true, false) => ranges.last_mut().unwrap().1 = pos,
+                _ => {}
+            }
+        }
+        (carrier, ranges)
+    }).collect::<HashMap<_, _>>();
+    let covered: i64 = doubles.values().flatten().map(|(from, to)| to - from).sum();
+
+    let mut crossings = HashSet::new();
+    segments.sort_unstable_by_key(|s| s.start.0);
+    let mut active: Vec<Segment> = Vec::new();
+    for s in segments {
+        active.retain(|a| a.end().0 >= s.start.0);
+        crossings.extend(active.iter().filter_map(|a| a.crossing(&s)));
+        active.push(s);
+    }
+
+    // a crossing is covered twice itself, but may already lie in the doubles of one or more carriers
+    let directions = doubles.keys().map(|(dir, _)| *dir).collect::<HashSet<_>>();
+    let correction: i64 = crossings.iter().map(|&p| {
+        let in_doubles = directions.iter().filter(|&&dir| {
+            let (carrier, pos) = Segment::carrier(dir, p);
+            doubles.get(&carrier).is_some_and(|r| r.iter().any(|&(from, to)| (from..to).contains(&pos)))
+        }).count() as i64;
+        1 - in_doubles
+    }).sum();
+    (covered + correction) as usize
+}
+
+#[aoc(day5, part2, bresenham)]
+pub fn part2_bresenham(inputs: &[Line]) -> usize {
+    overlaps(inputs, Raster::Bresenham)
+}
+
+#[aoc(day5, part1, sweep)]
+pub fn part1_sweep(inputs: &[Line]) -> usize {
+    overlaps_sweep(&inputs.iter().filter(|&((x1, y1), (x2, y2))| x1 == x2 || y1 == y2).copied().collect::<Vec<_>>())
+}
+
+#[aoc(day5, part2, sweep)]
+pub fn part2_sweep(inputs: &[Line]) -> usize {
+    overlaps_sweep(inputs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synth;
 
-    #[test]
-    pub fn test1() {
-        let input = generator("0,9 -> 5,9
+    const SAMPLE: &str = "0,9 -> 5,9
 8,0 -> 0,8
 9,4 -> 3,4
 2,2 -> 2,1
@@ -153,22 +427,73 @@ mod tests {
 0,9 -> 2,9
 3,4 -> 1,4
 0,0 -> 8,8
-5,5 -> 8,2");
+5,5 -> 8,2";
+
+    #[test]
+    pub fn test1() {
+        let input = generator(SAMPLE);
         assert_eq!(part1(&input), 5)
     }
 
     #[test]
     pub fn test2() {
-        let input = generator("0,9 -> 5,9
-8,0 -> 0,8
-9,4 -> 3,4
-2,2 -> 2,1
-7,0 -> 7,4
-6,4 -> 2,0
-0,9 -> 2,9
-3,4 -> 1,4
-0,0 -> 8,8
-5,5 -> 8,2");
+        let input = generator(SAMPLE);
         assert_eq!(part2(&input), 12)
     }
+
+    #[test]
+    pub fn test_sweep() {
+        let input = generator(SAMPLE);
+        assert_eq!(part1_sweep(&input), 5);
+        assert_eq!(part2_sweep(&input), 12);
+        for size in [10, 100, 1000] {
+            let input = generator(&synth::day5(300, size));
+            assert_eq!(part1_sweep(&input), part1(&input), "{}", size);
+            assert_eq!(part2_sweep(&input), part2(&input), "{}", size);
+        }
+
+        // an overlap of 2000001 points, a crossing inside it and one outside
+        let input = generator("0,0 -> 3000000,0\n5000000,0 -> 1000000,0\n2000000,-5 -> 2000000,5\n4000000,5 -> 4000000,-5\n7,7 -> 7,7");
+        assert_eq!(part2_sweep(&input), 2000002);
+    }
+
+    #[test]
+    pub fn test_sloped() {
+        assert_eq!(points(&((0, 0), (6, 4)), Raster::Lattice), vec![(0, 0), (3, 2), (6, 4)]);
+        assert_eq!(points(&((6, 4), (0, 0)), Raster::Bresenham), vec![(6, 4), (5, 3), (4, 3), (3, 2), (2, 1), (1, 1), (0, 0)]);
+        assert_eq!(points(&((2, 5), (1, 9)), Raster::Bresenham).len(), 5);
+        assert_eq!(points(&((3, 3), (3, 3)), Raster::Bresenham), vec![(3, 3)]);
+
+        let input = "0,0 -> 6,4\n0,2 -> 6,2\n3,0 -> 3,5\n0,4 -> 4,0\n1,1 -> 1,1";
+        let vents = vents(input, Raster::Bresenham);
+        assert_eq!(vents.iter().map(|v| v.kind).collect::<Vec<_>>(), vec![Kind::Sloped, Kind::Horizontal, Kind::Vertical, Kind::Diagonal, Kind::Horizontal]);
+        assert_eq!(vents.iter().map(|v| v.raster).collect::<Vec<_>>(), vec![Raster::Bresenham, Raster::Lattice, Raster::Lattice, Raster::Lattice, Raster::Lattice]);
+        assert_eq!(vents[0].points().len(), 7);
+
+        // (3, 2) is on three lines, the diagonal crosses the others at (2, 2) and (3, 1)
+        let input = generator(input);
+        assert_eq!(part2(&input), 3);
+        assert_eq!(part2_iter(&input), 3);
+        assert_eq!(part2_sweep(&input), 3);
+        assert_eq!(overlaps(&input, Raster::Lattice), 3);
+        // the Bresenham line also covers the single point at (1, 1)
+        assert_eq!(part2_bresenham(&input), 4);
+    }
+
+    #[test]
+    pub fn test_field() {
+        let field = Field::new(&generator(SAMPLE), Raster::Lattice);
+        assert_eq!(field.at_least(2), 12);
+        assert_eq!(field.at_least(3), 2);
+        assert_eq!(field.at_least(1), 39);
+        assert_eq!(field.hottest(3), vec![((4, 4), 3), ((6, 4), 3), ((0, 9), 2)]);
+        assert_eq!(field.at((2, 2)), 2);
+        assert_eq!(field.total((0, 0), (9, 9)), 53);
+        assert_eq!(field.total((7, 4), (4, 4)), 3 + 1 + 3 + 2);
+        assert_eq!(field.bounds(), Some(((0, 0), (9, 9))));
+
+        let field = Field::new(&generator("0,0 -> 2,0\n1,0 -> 1,1"), Raster::Lattice);
+        assert_eq!(field.to_csv(), "x,y,count\n0,0,1\n1,0,2\n2,0,1\n1,1,1\n");
+        assert_eq!(field.to_ppm(), "P3\n3 2\n255\n255 128 128 255 0 0 255 128 128\n255 255 255 255 128 128 255 255 255\n");
+    }
 }
@@ -1,13 +1,21 @@
+use std::fmt::{Display, Formatter};
+use std::num::{NonZeroU64, ParseIntError};
 
-#[aoc_generator(day6)]
-pub fn generator(input: &str) -> Vec<u64> {
+use crate::count::{BigUint, Count, Overflow, Solver, Width};
+
+pub fn parse(input: &str) -> Result<Vec<u64>, ParseIntError> {
     // parse all the ages
     let fishes = input
         .split(',')
-        .map(|x| x.parse::<u64>().unwrap()).collect::<Vec<_>>();
+        .map(|x| x.parse::<u64>()).collect::<Result<Vec<_>, _>>()?;
 
     // group ages into vec with index = age and value = count
-    (0..=8).into_iter().map(|age| fishes.iter().filter(|&x| *x == age).count() as u64).collect()
+    Ok((0..=8).into_iter().map(|age| fishes.iter().filter(|&x| *x == age).count() as u64).collect())
+}
+
+#[aoc_generator(day6)]
+pub fn generator(input: &str) -> Vec<u64> {
+    parse(input).unwrap()
 }
 
 pub fn run(inputs: &[u64], n: u32) -> u64 {
@@ -52,9 +60,226 @@ pub fn part2(inputs: &[u64]) -> u64 {
     run_iter(inputs, 256)
 }
 
+/// [`run`] with checked arithmetic in a count type of choice.
+pub fn run_checked<C: Count>(inputs: &[u64], n: u64) -> Result<C, Overflow> {
+    let mut v = inputs.iter().map(|&x| C::from_u64(x)).collect::<Vec<C>>();
+    for _ in 0..n {
+        v.rotate_left(1);
+        v[6] = v[6].try_add(&v[8])?;
+    }
+    v.iter().try_fold(C::zero(), |acc, x| acc.try_add(x))
+}
+
+pub fn run_width(inputs: &[u64], n: u64, width: Width) -> Result<String, Overflow> {
+    struct Run<'a>(&'a [u64], u64);
+    impl Solver for Run<'_> {
+        fn solve<C: Count>(&self) -> Result<C, Overflow> {
+            run_checked(self.0, self.1)
+        }
+    }
+    width.solve(&Run(inputs, n))
+}
+
+#[aoc(day6, part2, big)]
+pub fn part2_big(inputs: &[u64]) -> BigUint {
+    run_checked(inputs, 256).unwrap()
+}
+
+/// Rules of a population where every fish counts down a timer and spawns when it passes 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LifecycleModel {
+    /// days between two spawns of the same fish
+    pub cycle: usize,
+    /// extra days a newborn needs before its first cycle
+    pub newborn_delay: usize,
+    /// newborns per spawn
+    pub offspring: u64,
+    /// fish die right after spawning this many times
+    pub lifespan: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleError {
+    ZeroCycle,
+    ZeroLifespan,
+    /// some fish start with a timer the model has no bucket for
+    TimerOutOfRange { timer: usize, buckets: usize },
+    /// the population no longer fits into a u64 on this day
+    Overflow { day: u32 },
+}
+
+impl Display for LifecycleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LifecycleError::ZeroCycle => write!(f, "cycle has to be at least one day"),
+            LifecycleError::ZeroLifespan => write!(f, "lifespan has to allow at least one spawn"),
+            LifecycleError::TimerOutOfRange { timer, buckets } => write!(f, "timer {} does not exist in a model with {} timers", timer, buckets),
+            LifecycleError::Overflow { day } => write!(f, "population overflows on day {}", day),
+        }
+    }
+}
+
+impl std::error::Error for LifecycleError {}
+
+impl LifecycleModel {
+    /// The puzzle, a 7 day cycle with newborns starting at 8.
+    pub fn lanternfish() -> Self {
+        LifecycleModel { cycle: 7, newborn_delay: 2, offspring: 1, lifespan: None }
+    }
+
+    /// Number of timer values, newborns start at the highest.
+    pub fn buckets(&self) -> usize {
+        self.cycle + self.newborn_delay
+    }
+
+    fn validate(&self, inputs: &[u64]) -> Result<(), LifecycleError> {
+        if self.cycle == 0 {
+            return Err(LifecycleError::ZeroCycle);
+        }
+        if self.lifespan == Some(0) {
+            return Err(LifecycleError::ZeroLifespan);
+        }
+        let buckets = self.buckets();
+        match inputs.iter().skip(buckets).position(|&x| x != 0) {
+            Some(i) => Err(LifecycleError::TimerOutOfRange { timer: buckets + i, buckets }),
+            None => Ok(()),
+        }
+    }
+
+    /// Fish per timer value for every day from 0 to `n`, `inputs` counting the fish per timer at day 0.
+    /// Fails on the first day the whole population no longer fits into a u64.
+    pub fn series(&self, inputs: &[u64], n: u32) -> Result<Vec<Vec<u64>>, LifecycleError> {
+        self.validate(inputs)?;
+        let buckets = self.buckets();
+
+        // fish by spawns so far and timer, spawns are only tracked when fish can die
+        let lives = self.lifespan.unwrap_or(1) as usize;
+        let mut state = vec![vec![0u64; buckets]; lives];
+        state[0].iter_mut().zip(inputs).for_each(|(s, &x)| *s = x);
+
+        let totals = |state: &Vec<Vec<u64>>, day: u32| {
+            let overflow = LifecycleError::Overflow { day };
+            let totals = (0..buckets)
+                .map(|t| state.iter().try_fold(0u64, |acc, s| acc.checked_add(s[t])).ok_or(overflow))
+                .collect::<Result<Vec<u64>, _>>()?;
+            // the buckets can fit long after their sum does not
+            totals.iter().try_fold(0u64, |acc, &x| acc.checked_add(x)).ok_or(overflow)?;
+            Ok(totals)
+        };
+        let mut series = vec![totals(&state, 0)?];
+        for day in 1..=n {
+            let overflow = LifecycleError::Overflow { day };
+            let add = |cell: &mut u64, x: u64| cell.checked_add(x).map(|sum| *cell = sum).ok_or(overflow);
+            let mut next = vec![vec![0u64; buckets]; lives];
+            for (spawns, s) in state.iter().enumerate() {
+                for t in 1..buckets {
+                    add(&mut next[spawns][t - 1], s[t])?;
+                }
+                add(&mut next[0][buckets - 1], s[0].checked_mul(self.offspring).ok_or(overflow)?)?;
+                match self.lifespan {
+                    Some(lifespan) if spawns + 1 >= lifespan as usize => {}
+                    Some(_) => add(&mut next[spawns + 1][self.cycle - 1], s[0])?,
+                    None => add(&mut next[spawns][self.cycle - 1], s[0])?,
+                }
+            }
+            state = next;
+            series.push(totals(&state, day)?);
+        }
+        Ok(series)
+    }
+
+    pub fn run(&self, inputs: &[u64], n: u32) -> Result<u64, LifecycleError> {
+        Ok(self.series(inputs, n)?.last().unwrap().iter().sum())
+    }
+}
+
+#[aoc(day6, part1, model)]
+pub fn part1_model(inputs: &[u64]) -> u64 {
+    LifecycleModel::lanternfish().run(inputs, 80).unwrap()
+}
+
+#[aoc(day6, part2, model)]
+pub fn part2_model(inputs: &[u64]) -> u64 {
+    LifecycleModel::lanternfish().run(inputs, 256).unwrap()
+}
+
+type Matrix<T> = Vec<Vec<T>>;
+
+/// One day as a matrix, `next[to] = sum of m[to][from] * today[from]` over the 9 ages.
+pub fn transition() -> Matrix<u64> {
+    let mut m = vec![vec![0; 9]; 9];
+    for age in 1..=8 {
+        m[age - 1][age] = 1;
+    }
+    m[6][0] = 1;
+    m[8][0] = 1;
+    m
+}
+
+// `mul_add(acc, a, b)` is `acc + a * b` in the chosen arithmetic
+fn mat_mul<T: Clone, E>(a: &Matrix<T>, b: &Matrix<T>, zero: &T, mul_add: &impl Fn(&T, &T, &T) -> Result<T, E>) -> Result<Matrix<T>, E> {
+    (0..a.len()).map(|i| {
+        (0..b[0].len()).map(|j| (0..b.len()).try_fold(zero.clone(), |acc, k| mul_add(&acc, &a[i][k], &b[k][j]))).collect()
+    }).collect()
+}
+
+/// `m^n * v` by repeated squaring.
+fn mat_pow_vec<T: Clone, E>(m: Matrix<T>, mut n: u64, v: Vec<T>, zero: T, mul_add: impl Fn(&T, &T, &T) -> Result<T, E>) -> Result<Vec<T>, E> {
+    let mut v = v.into_iter().map(|x| vec![x]).collect::<Matrix<T>>();
+    let mut m = m;
+    while n > 0 {
+        if n & 1 == 1 {
+            v = mat_mul(&m, &v, &zero, &mul_add)?;
+        }
+        n >>= 1;
+        if n > 0 {
+            m = mat_mul(&m, &m, &zero, &mul_add)?;
+        }
+    }
+    Ok(v.into_iter().map(|mut row| row.remove(0)).collect())
+}
+
+/// Same as [`run_checked`] in O(log n) steps. The powers of the transition can overflow a little
+/// before the population does.
+pub fn run_matrix<C: Count>(inputs: &[u64], n: u64) -> Result<C, Overflow> {
+    let m = transition().into_iter().map(|row| row.into_iter().map(C::from_u64).collect()).collect();
+    let v = inputs.iter().map(|&x| C::from_u64(x)).collect();
+    let v = mat_pow_vec(m, n, v, C::zero(), |acc: &C, a: &C, b: &C| acc.try_add(&a.try_mul(b)?))?;
+    v.iter().try_fold(C::zero(), |acc, x| acc.try_add(x))
+}
+
+/// Population after `n` days modulo `modulus`, feasible for any `n`.
+pub fn run_matrix_mod(inputs: &[u64], n: u64, modulus: NonZeroU64) -> u64 {
+    let m = modulus.get() as u128;
+    let v = inputs.iter().map(|&x| (x as u128 % m) as u64).collect();
+    let v = mat_pow_vec(transition(), n, v, 0, |&acc: &u64, &a: &u64, &b: &u64| {
+        Ok::<_, std::convert::Infallible>(((acc as u128 + a as u128 * b as u128) % m) as u64)
+    }).unwrap();
+    v.iter().fold(0, |acc, &x| ((acc as u128 + x as u128) % m) as u64)
+}
+
+#[aoc(day6, part1, matrix)]
+pub fn part1_matrix(inputs: &[u64]) -> u64 {
+    run_matrix(inputs, 80).unwrap()
+}
+
+#[aoc(day6, part2, matrix)]
+pub fn part2_matrix(inputs: &[u64]) -> u64 {
+    run_matrix(inputs, 256).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_monotone(inputs in prop::collection::vec(0..1000u64, 9), n in 0..300u32) {
+            prop_assert!(run(&inputs, n) <= run(&inputs, n + 1));
+            prop_assert!(run_iter(&inputs, n) <= run_iter(&inputs, n + 1));
+        }
+    }
 
     #[test]
     pub fn test1() {
@@ -67,4 +292,69 @@ mod tests {
         let input = generator("3,4,3,1,2");
         assert_eq!(part2(&input), 26984457539)
     }
+
+    #[test]
+    pub fn test_checked() {
+        let input = generator("3,4,3,1,2");
+        assert_eq!(run_checked::<u64>(&input, 256), Ok(26984457539));
+        assert_eq!(part2_big(&input).to_string(), "26984457539");
+        assert_eq!(run_checked::<u64>(&input, 500), Err(Overflow));
+        assert_eq!(run_checked::<u128>(&input, 500).unwrap().to_string(), run_width(&input, 500, Width::Big).unwrap());
+        assert_eq!(run_width(&input, 1100, Width::U128), Err(Overflow));
+        assert!(run_width(&input, 1100, Width::Big).is_ok());
+    }
+
+    #[test]
+    pub fn test_matrix() {
+        let input = generator("3,4,3,1,2");
+        assert_eq!(part1_matrix(&input), 5934);
+        assert_eq!(part2_matrix(&input), 26984457539);
+        for n in [0, 1, 18, 80, 256] {
+            assert_eq!(run_matrix::<u64>(&input, n), Ok(run(&input, n as u32)));
+        }
+        assert_eq!(run_matrix::<BigUint>(&input, 1100), run_checked::<BigUint>(&input, 1100));
+        assert_eq!(run_matrix::<u128>(&input, 1100), Err(Overflow));
+
+        const P: u64 = 1_000_000_007;
+        let p = NonZeroU64::new(P).unwrap();
+        assert_eq!(run_matrix_mod(&input, 256, p), 26984457539 % P);
+        assert_eq!(run_matrix_mod(&input, 500, p) as u128, run_checked::<u128>(&input, 500).unwrap() % P as u128);
+        // 10^12 days only take about 40 squarings
+        assert!(run_matrix_mod(&input, 1_000_000_000_000, p) < P);
+        assert_eq!(run_matrix_mod(&input, 1_000_000_000_000, NonZeroU64::new(1).unwrap()), 0);
+    }
+
+    #[test]
+    pub fn test_model() {
+        let input = generator("3,4,3,1,2");
+        assert_eq!(part1_model(&input), 5934);
+        assert_eq!(part2_model(&input), 26984457539);
+
+        let series = LifecycleModel::lanternfish().series(&input, 18).unwrap();
+        assert_eq!(series.len(), 19);
+        assert_eq!(series[0], input);
+        // 6,0,6,4,5,6,0,1,1,2,6,0,1,1,1,2,2,3,3,4,6,7,8,8,8,8 after 18 days
+        assert_eq!(series[18], vec![3, 5, 3, 2, 2, 1, 5, 1, 4]);
+
+        // every fish splits into two after two days
+        let split = LifecycleModel { cycle: 2, newborn_delay: 0, offspring: 2, lifespan: Some(1) };
+        let series = split.series(&[1], 6).unwrap();
+        assert_eq!(series.iter().map(|d| d.iter().sum()).collect::<Vec<u64>>(), vec![1, 2, 2, 4, 4, 8, 8]);
+        assert_eq!(series[5], vec![0, 8]);
+
+        // a fish dies after its second spawn, which keeps the population at two fish
+        let steady = LifecycleModel { cycle: 3, newborn_delay: 3, offspring: 1, lifespan: Some(2) };
+        assert_eq!(steady.run(&[1, 0, 0, 0, 0, 0], 6), Ok(2));
+    }
+
+    #[test]
+    pub fn test_model_errors() {
+        let input = generator("3,4,3,1,2");
+        let fish = LifecycleModel::lanternfish();
+        assert_eq!(LifecycleModel { cycle: 0, ..fish }.run(&input, 1), Err(LifecycleError::ZeroCycle));
+        assert_eq!(LifecycleModel { lifespan: Some(0), ..fish }.run(&input, 1), Err(LifecycleError::ZeroLifespan));
+        assert_eq!(LifecycleModel { newborn_delay: 0, ..fish }.series(&generator("8,1"), 1), Err(LifecycleError::TimerOutOfRange { timer: 8, buckets: 7 }));
+        assert_eq!(fish.run(&input, 489), Ok(run_checked::<u64>(&input, 489).unwrap()));
+        assert_eq!(fish.run(&input, 500), Err(LifecycleError::Overflow { day: 490 }));
+    }
 }
@@ -1,10 +1,16 @@
-use statistical::{mean, median};
+use itertools::Itertools;
+use statistical::median;
+use std::num::ParseIntError;
 
-#[aoc_generator(day7)]
-pub fn generator(input: &str) -> Vec<i32> {
+pub fn parse(input: &str) -> Result<Vec<i32>, ParseIntError> {
     input
         .split(',')
-        .map(|x| x.parse::<i32>().unwrap()).collect()
+        .map(|x| x.parse::<i32>()).collect()
+}
+
+#[aoc_generator(day7)]
+pub fn generator(input: &str) -> Vec<i32> {
+    parse(input).unwrap()
 }
 
 #[aoc(day7, part1)]
@@ -13,24 +19,103 @@ pub fn part1(inputs: &[i32]) -> i32 {
     inputs.iter().map(|x| (median - x).abs()).sum()
 }
 
-#[aoc(day7, part2)]
-pub fn part2(inputs: &[i32]) -> i32 {
-    let m = mean(inputs.clone().iter().map(|&x| x as f32).collect::<Vec<_>>().as_slice()).floor() as i32;
-    inputs.clone().into_iter().map(|&x| (1..=((m - x).abs())).into_iter().sum::<i32>()).sum()
-    /*(m-2..=m+2).into_iter()
-        .map(|m| {
-            inputs
-                .clone()
-                .into_iter()
-                .map(|&x|
-                    (1..=((m - x).abs()))
-                        .into_iter()
-                        .sum::<i32>()
-                ).sum()
-        })
-        .min()
-        .unwrap()*/
+/// Fuel for moving one step per unit of distance.
+pub fn linear(d: i64) -> i64 {
+    d
+}
+
+/// Fuel for moving where every step costs one more than the last.
+pub fn triangular(d: i64) -> i64 {
+    d * (d + 1) / 2
+}
+
+pub fn quadratic(d: i64) -> i64 {
+    d * d
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i32,
+    pub fuel: i64,
+}
 
+/// Total fuel for all crabs to move to `position`.
+pub fn fuel<F: Fn(i64) -> i64>(inputs: &[i32], cost: &F, position: i32) -> i64 {
+    inputs.iter().map(|&x| cost((position - x).abs() as i64)).sum()
+}
+
+/// Cheapest position for a cost that is convex and non decreasing in the distance, the leftmost on ties.
+///
+/// The total is then convex in the position, so a binary search for the first position where it stops
+/// decreasing finds the optimum with O(n log range) cost evaluations.
+pub fn align<F: Fn(i64) -> i64>(inputs: &[i32], cost: F) -> Alignment {
+    let (mut lo, mut hi) = (*inputs.iter().min().unwrap(), *inputs.iter().max().unwrap());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if fuel(inputs, &cost, mid + 1) >= fuel(inputs, &cost, mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Alignment { position: lo, fuel: fuel(inputs, &cost, lo) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crab {
+    pub position: i32,
+    /// fuel use multiplier
+    pub weight: i64,
+}
+
+pub fn crabs(inputs: &[i32]) -> Vec<Crab> {
+    inputs.iter().map(|&position| Crab { position, weight: 1 }).collect()
+}
+
+pub fn weighted_fuel<F: Fn(i64) -> i64>(crabs: &[Crab], cost: &F, position: i32) -> i64 {
+    crabs.iter().map(|c| c.weight * cost((position - c.position).abs() as i64)).sum()
+}
+
+/// Lowest position with at least half of the weight at or below it, optimal for linear costs.
+pub fn weighted_median(crabs: &[Crab]) -> i32 {
+    let mut sorted = crabs.to_vec();
+    sorted.sort_unstable_by_key(|c| c.position);
+    let total: i64 = sorted.iter().map(|c| c.weight).sum();
+    let mut below = 0;
+    sorted.iter().find(|c| {
+        below += c.weight;
+        2 * below >= total
+    }).unwrap().position
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub position: i32,
+    pub fuel: i64,
+    /// fuel for every candidate position, in order
+    pub curve: Vec<(i32, i64)>,
+}
+
+/// Cheapest of the allowed target positions, or of all positions between the crabs without a restriction.
+/// `None` without any candidate.
+pub fn plan<F: Fn(i64) -> i64>(crabs: &[Crab], targets: Option<&[i32]>, cost: F) -> Option<Plan> {
+    let mut candidates = match targets {
+        Some(targets) => targets.to_vec(),
+        None => {
+            let (lo, hi) = crabs.iter().map(|c| c.position).minmax().into_option()?;
+            (lo..=hi).collect()
+        }
+    };
+    candidates.sort_unstable();
+    candidates.dedup();
+    let curve = candidates.into_iter().map(|p| (p, weighted_fuel(crabs, &cost, p))).collect::<Vec<_>>();
+    let &(position, fuel) = curve.iter().min_by_key(|&&(_, fuel)| fuel)?;
+    Some(Plan { position, fuel, curve })
+}
+
+#[aoc(day7, part2)]
+pub fn part2(inputs: &[i32]) -> i64 {
+    align(inputs, triangular).fuel
 }
 
 #[cfg(test)]
@@ -48,4 +133,48 @@ mod tests {
         let input = generator("16,1,2,0,4,2,7,1,2,14");
         assert_eq!(part2(&input), 168)
     }
+
+    #[test]
+    pub fn test_align() {
+        let input = generator("16,1,2,0,4,2,7,1,2,14");
+        assert_eq!(align(&input, linear), Alignment { position: 2, fuel: 37 });
+        assert_eq!(align(&input, triangular), Alignment { position: 5, fuel: 168 });
+        assert_eq!(align(&input, quadratic).position, 5);
+        // user defined, moving is free up to 3 steps
+        assert_eq!(align(&input, |d| (d - 3).max(0)), Alignment { position: 4, fuel: 9 + 1 + 7 });
+
+        // the floor of the mean 4.9 is off by one
+        assert_eq!(fuel(&input, &triangular, 4), 170);
+        for p in -5..20 {
+            assert!(fuel(&input, &triangular, p) >= part2(&input));
+        }
+    }
+
+    #[test]
+    pub fn test_plan() {
+        let input = generator("16,1,2,0,4,2,7,1,2,14");
+        let crabs = crabs(&input);
+        // the plain case is the median
+        let plain = plan(&crabs, None, linear).unwrap();
+        assert_eq!((plain.position, plain.fuel), (weighted_median(&crabs), part1(&input) as i64));
+        assert_eq!(plain.curve.len(), 17);
+        assert_eq!(plain.curve[..3], [(0, 49), (1, 41), (2, 37)]);
+        assert_eq!(plan(&crabs, None, triangular).unwrap().fuel, part2(&input));
+
+        // only odd docking slots
+        let slots = [9, 1, 3, 5, 7];
+        let docked = plan(&crabs, Some(&slots), triangular).unwrap();
+        assert_eq!((docked.position, docked.fuel), (5, 168));
+        assert_eq!(docked.curve.iter().map(|c| c.0).collect::<Vec<_>>(), vec![1, 3, 5, 7, 9]);
+        let docked = plan(&crabs, Some(&slots), linear).unwrap();
+        assert_eq!((docked.position, docked.fuel), (3, 39));
+        assert_eq!(plan(&crabs, Some(&[]), linear), None);
+
+        // a heavy crab pulls the target to itself
+        let mut weighted = crabs.clone();
+        weighted[0].weight = 20;
+        assert_eq!(weighted_median(&weighted), 16);
+        assert_eq!(plan(&weighted, None, linear).unwrap().position, 16);
+        assert!(plan(&weighted, None, triangular).unwrap().position > 5);
+    }
 }
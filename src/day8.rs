@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use itertools::Itertools;
 
 #[aoc_generator(day8)]
@@ -58,9 +59,162 @@ pub fn part2(inputs: &[(Vec<String>, Vec<String>)]) -> i32 {
         .sum()
 }
 
+/// Segments of the digits 0 to 9, bit 0 being segment a.
+const DIGITS: [u8; 10] = [0b1110111, 0b0100100, 0b1011101, 0b1101101, 0b0101110, 0b1101011, 0b1111011, 0b0100101, 0b1111111, 0b1101111];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// `mapping[i]` is the segment driven by wire `'a' + i`
+    pub mapping: [char; 7],
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// letters outside a to g or a wire used twice
+    InvalidPattern(String),
+    /// no wiring fits, listing the patterns without which one would, or all of them if no single one is to blame
+    Contradiction { patterns: Vec<String> },
+    /// several wirings fit, listing the patterns that decode to different digits between them
+    Ambiguous { wirings: usize, patterns: Vec<String> },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidPattern(p) => write!(f, "invalid pattern {}", p),
+            DecodeError::Contradiction { patterns } => write!(f, "no wiring fits, check {}", patterns.join(" ")),
+            DecodeError::Ambiguous { wirings, patterns } => write!(f, "{} wirings fit, ambiguous {}", wirings, patterns.join(" ")),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn wires(pattern: &str) -> Result<u8, DecodeError> {
+    pattern.chars().try_fold(0u8, |acc, c| match c {
+        'a'..='g' if acc & 1 << (c as u8 - b'a') == 0 => Ok(acc | 1 << (c as u8 - b'a')),
+        _ => Err(DecodeError::InvalidPattern(pattern.to_string())),
+    })
+}
+
+/// `wiring[wire]` is the segment index the wire drives.
+type Wiring = [u8; 7];
+
+fn digit(wiring: &Wiring, wires: u8) -> Option<u32> {
+    let segments = (0..7).filter(|w| wires >> w & 1 == 1).fold(0u8, |acc, w| acc | 1 << wiring[w]);
+    DIGITS.iter().position(|&d| d == segments).map(|d| d as u32)
+}
+
+fn fits(wiring: &Wiring, patterns: &[u8]) -> bool {
+    patterns.iter().all(|&p| digit(wiring, p).is_some())
+}
+
+/// Narrow the segments each wire can drive from the pattern lengths, then search what is left.
+fn solve_propagate(patterns: &[u8]) -> Vec<Wiring> {
+    let mut candidates = [0x7fu8; 7];
+    for &p in patterns {
+        // a pattern lights the segments of one of the digits of its length and leaves the others dark
+        let (lit, dark) = DIGITS.iter().filter(|d| d.count_ones() == p.count_ones()).fold((0, 0), |(l, d), &digit| (l | digit, d | (!digit & 0x7f)));
+        for (w, c) in candidates.iter_mut().enumerate() {
+            *c &= if p >> w & 1 == 1 { lit } else { dark };
+        }
+    }
+    // wires down to a single segment take it away from the others
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for w in 0..7 {
+            if candidates[w].count_ones() != 1 {
+                continue;
+            }
+            let taken = candidates[w];
+            for (o, c) in candidates.iter_mut().enumerate() {
+                if o != w && *c & taken != 0 {
+                    *c &= !taken;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    fn search(candidates: &[u8; 7], patterns: &[u8], wiring: &mut Wiring, w: usize, used: u8, found: &mut Vec<Wiring>) {
+        if w == 7 {
+            if fits(wiring, patterns) {
+                found.push(*wiring);
+            }
+            return;
+        }
+        for s in (0..7).filter(|s| (candidates[w] & !used) >> s & 1 == 1) {
+            wiring[w] = s;
+            search(candidates, patterns, wiring, w + 1, used | 1 << s, found);
+        }
+    }
+    let mut found = Vec::new();
+    search(&candidates, patterns, &mut [0; 7], 0, 0, &mut found);
+    found
+}
+
+/// Try all 5040 wirings.
+fn solve_permutations(patterns: &[u8]) -> Vec<Wiring> {
+    (0..7u8).permutations(7)
+        .map(|p| [p[0], p[1], p[2], p[3], p[4], p[5], p[6]])
+        .filter(|wiring| fits(wiring, patterns))
+        .collect()
+}
+
+fn decode_with(observed: &[String], output: &[String], solve: fn(&[u8]) -> Vec<Wiring>) -> Result<Decoded, DecodeError> {
+    let all = observed.iter().chain(output).collect::<Vec<_>>();
+    let patterns = all.iter().map(|p| wires(p)).collect::<Result<Vec<_>, _>>()?;
+    let wirings = solve(&patterns);
+    match wirings[..] {
+        [] => {
+            let culprits = (0..patterns.len())
+                .filter(|&i| !solve(&[&patterns[..i], &patterns[i + 1..]].concat()).is_empty())
+                .map(|i| all[i].to_string())
+                .collect::<Vec<_>>();
+            let patterns = if culprits.is_empty() { all.iter().map(|p| p.to_string()).collect() } else { culprits };
+            Err(DecodeError::Contradiction { patterns })
+        }
+        [wiring] => Ok(Decoded {
+            mapping: wiring.map(|s| (b'a' + s) as char),
+            value: output.iter().fold(0, |acc, p| acc * 10 + digit(&wiring, wires(p).unwrap()).unwrap()),
+        }),
+        _ => Err(DecodeError::Ambiguous {
+            wirings: wirings.len(),
+            patterns: all.iter().zip(&patterns)
+                .filter(|(_, &p)| !wirings.iter().map(|w| digit(w, p)).all_equal())
+                .map(|(s, _)| s.to_string())
+                .unique()
+                .collect(),
+        }),
+    }
+}
+
+/// Find the wiring by constraint propagation, the output patterns constrain it as well.
+pub fn decode(observed: &[String], output: &[String]) -> Result<Decoded, DecodeError> {
+    decode_with(observed, output, solve_propagate)
+}
+
+/// Same as [decode] by checking every permutation of the wires.
+pub fn decode_permutations(observed: &[String], output: &[String]) -> Result<Decoded, DecodeError> {
+    decode_with(observed, output, solve_permutations)
+}
+
+#[aoc(day8, part2, propagate)]
+pub fn part2_propagate(inputs: &[(Vec<String>, Vec<String>)]) -> u32 {
+    inputs.iter().map(|(obs, out)| decode(obs, out).unwrap().value).sum()
+}
+
+#[aoc(day8, part2, permutations)]
+pub fn part2_permutations(inputs: &[(Vec<String>, Vec<String>)]) -> u32 {
+    inputs.iter().map(|(obs, out)| decode_permutations(obs, out).unwrap().value).sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::synth;
 
     #[test]
     pub fn test1() {
@@ -93,4 +247,38 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
         assert_eq!(part2(&input), 61229);
         //assert_eq!(part2(&testinput), 5353)
     }
+
+    fn line(observed: &str, output: &str) -> (Vec<String>, Vec<String>) {
+        generator(&format!("{} | {}", observed, output)).remove(0)
+    }
+
+    #[test]
+    pub fn test_decode() {
+        let (obs, out) = line("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab", "cdfeb fcadb cdfeb cdbaf");
+        let decoded = Decoded { mapping: ['c', 'f', 'g', 'a', 'b', 'd', 'e'], value: 5353 };
+        assert_eq!(decode(&obs, &out), Ok(decoded.clone()));
+        assert_eq!(decode_permutations(&obs, &out), Ok(decoded));
+
+        let input = generator(&synth::day8(20));
+        assert_eq!(part2_propagate(&input), part2(&input) as u32);
+        assert_eq!(part2_permutations(&input), part2(&input) as u32);
+    }
+
+    #[test]
+    pub fn test_errors() {
+        // 7 replaced by a pattern no wiring lights up together with the rest
+        let (obs, out) = line("acedgfb cdfbe gcdfa fbcad dag cefabd cdfgeb eafb cagedb ab", "cdfeb fcadb cdfeb cdbaf");
+        assert_eq!(decode(&obs, &out), Err(DecodeError::Contradiction { patterns: vec!["dag".to_string()] }));
+        assert_eq!(decode_permutations(&obs, &out), decode(&obs, &out));
+
+        // the 5 segment pattern can be a 2, 3 or 5
+        let (obs, out) = line("abcdefg abcde", "abcde");
+        let err = decode(&obs, &out).unwrap_err();
+        assert!(matches!(&err, DecodeError::Ambiguous { patterns, .. } if patterns == &vec!["abcde".to_string()]));
+        assert_eq!(decode_permutations(&obs, &out), Err(err));
+
+        let (obs, out) = line("ab abx", "ab");
+        assert_eq!(decode(&obs, &out), Err(DecodeError::InvalidPattern("abx".to_string())));
+        assert_eq!(decode(&obs[..1], &["aa".to_string()]), Err(DecodeError::InvalidPattern("aa".to_string())));
+    }
 }
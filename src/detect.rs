@@ -0,0 +1,187 @@
+use std::sync::OnceLock;
+use regex::Regex;
+
+use crate::{day1, day16, day17, day2, day3, day4, day6, day7};
+
+/// A day the input was accepted for. Confidences of all candidates sum up to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub day: u8,
+    pub confidence: f64,
+}
+
+/// A day the input was rejected for, together with the reason. Days with a fallible parser give
+/// its error once the input looks right, the others only check the layout and give no parse error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub day: u8,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub candidates: Vec<Candidate>,
+    pub rejected: Vec<Rejection>,
+}
+
+impl Detection {
+    pub fn best(&self) -> Option<&Candidate> {
+        self.candidates.first()
+    }
+
+    pub fn days(&self) -> Vec<u8> {
+        self.candidates.iter().map(|c| c.day).collect()
+    }
+}
+
+type Validate = fn(&str) -> Result<(), String>;
+
+struct Rule {
+    day: u8,
+    // the whole (trimmed) input has to match this
+    layout: Regex,
+    // how distinctive the layout is, generic number lists score low
+    specificity: f64,
+    // stricter check for days with a fallible parser, only run once the layout matched
+    validate: Option<Validate>,
+}
+
+fn layout(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap()
+}
+
+/// Repeat a line pattern over all lines of the input.
+fn lines(line: &str) -> Regex {
+    layout(&format!(r"^(?:{0})(?:\n(?:{0}))*$", line))
+}
+
+// compiled once, the CLI detects many files in one run
+static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+
+fn rules() -> &'static [Rule] {
+    RULES.get_or_init(build_rules)
+}
+
+fn build_rules() -> Vec<Rule> {
+    let cuboid = r"(?:on|off) x=-?\d+\.\.-?\d+,y=-?\d+\.\.-?\d+,z=-?\d+\.\.-?\d+";
+    let scanner = r"--- scanner \d+ ---(?:\n-?\d+,-?\d+,-?\d+)+";
+    vec![
+        Rule { day: 1, layout: lines(r"-?\d+"), specificity: 0.3, validate: Some(|i| day1::parse(i).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 2, layout: lines(r"(?:forward|down|up) \d+"), specificity: 1.0, validate: Some(|i| day2::parse(i, day2::Dialect::Standard).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 3, layout: lines(r"[01]+"), specificity: 0.5, validate: Some(|i| day3::Report::parse(i).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 4, layout: layout(r"^\d+(?:,\d+)*\n\n+[ \d]+(?:\n+[ \d]+)*$"), specificity: 1.0, validate: Some(|i| day4::Input::parse(i).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 5, layout: lines(r"\d+,\d+ -> \d+,\d+"), specificity: 1.0, validate: None },
+        Rule { day: 6, layout: layout(r"^[0-8](?:,[0-8])*$"), specificity: 0.6, validate: Some(|i| day6::parse(i).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 7, layout: layout(r"^\d+(?:,\d+)*$"), specificity: 0.4, validate: Some(|i| day7::parse(i).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 8, layout: lines(r"[a-g]+(?: [a-g]+){9} \|(?: [a-g]+){4}"), specificity: 1.0, validate: None },
+        Rule { day: 9, layout: lines(r"\d+"), specificity: 0.3, validate: None },
+        Rule { day: 10, layout: lines(r"[()\[\]{}<>]+"), specificity: 0.8, validate: None },
+        Rule { day: 11, layout: layout(r"^\d{10}(?:\n\d{10}){9}$"), specificity: 0.6, validate: None },
+        Rule { day: 12, layout: lines(r"\w+-\w+"), specificity: 0.8, validate: None },
+        Rule { day: 13, layout: layout(r"^\d+,\d+(?:\n\d+,\d+)*\n\nfold along [xy]=\d+(?:\nfold along [xy]=\d+)*$"), specificity: 1.0, validate: None },
+        Rule { day: 14, layout: layout(r"^[A-Z]+\n\n[A-Z]{2} -> [A-Z](?:\n[A-Z]{2} -> [A-Z])*$"), specificity: 1.0, validate: None },
+        Rule { day: 15, layout: lines(r"\d+"), specificity: 0.3, validate: None },
+        Rule { day: 16, layout: layout(r"^[0-9A-F]+$"), specificity: 0.5, validate: Some(|i| day16::parse(i).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 17, layout: layout(r"^target area: x=-?\d+\.\.-?\d+, y=-?\d+\.\.-?\d+$"), specificity: 1.0, validate: Some(|i| day17::parse(i).map(drop).map_err(|e| e.to_string())) },
+        Rule { day: 18, layout: lines(r"\[[\[\]\d,]*\]"), specificity: 0.9, validate: None },
+        Rule { day: 19, layout: layout(&format!(r"^{0}(?:\n\n{0})*$", scanner)), specificity: 1.0, validate: None },
+        Rule { day: 20, layout: layout(r"^[#.]+\n\n[#.]+(?:\n[#.]+)*$"), specificity: 0.9, validate: None },
+        Rule { day: 21, layout: layout(r"^Player 1 starting position: \d+\nPlayer 2 starting position: \d+$"), specificity: 1.0, validate: None },
+        Rule { day: 22, layout: lines(cuboid), specificity: 1.0, validate: None },
+        Rule { day: 23, layout: layout(r"^#+\n#\.+#(?:\n[ #A-D]+)+$"), specificity: 1.0, validate: None },
+    ]
+}
+
+/// Check an input against all generators and report which days it is valid for.
+pub fn detect(input: &str) -> Detection {
+    let input = input.replace("\r\n", "\n");
+    let input = input.trim();
+    let mut candidates = Vec::new();
+    let mut rejected = Vec::new();
+
+    for rule in rules() {
+        if !rule.layout.is_match(input) {
+            rejected.push(Rejection { day: rule.day, reason: format!("does not look like a day{} input", rule.day) });
+            continue;
+        }
+        match rule.validate.map_or(Ok(()), |validate| validate(input)) {
+            Err(reason) => rejected.push(Rejection { day: rule.day, reason }),
+            Ok(()) => candidates.push(Candidate { day: rule.day, confidence: rule.specificity }),
+        }
+    }
+
+    let total: f64 = candidates.iter().map(|c| c.confidence).sum();
+    candidates.iter_mut().for_each(|c| c.confidence /= total);
+    candidates.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap().then(a.day.cmp(&b.day)));
+
+    Detection { candidates, rejected }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_unique() {
+        let examples = [
+            ("target area: x=20..30, y=-10..-5", 17),
+            ("--- scanner 0 ---\n404,-588,-901\n528,-643,409\n\n--- scanner 1 ---\n686,422,578", 19),
+            ("on x=10..12,y=10..12,z=10..12\noff x=9..11,y=9..11,z=9..11", 22),
+            ("forward 5\ndown 5\nforward 8\nup 3", 2),
+            ("0,9 -> 5,9\n8,0 -> 0,8", 5),
+            ("NNCB\n\nCH -> B\nHH -> N\nCB -> H\nNH -> C\nHB -> C\nHC -> B\nHN -> C\nNN -> C\nBH -> H\nNC -> B\nNB -> B\nBN -> B\nBB -> N\nBC -> B\nCC -> N\nCN -> C", 14),
+            ("6,10\n0,14\n\nfold along y=7\nfold along x=5", 13),
+            ("Player 1 starting position: 4\nPlayer 2 starting position: 8", 21),
+        ];
+
+        for (input, day) in examples {
+            let detection = detect(input);
+            assert_eq!(detection.days(), vec![day], "{}", input);
+            assert_eq!(detection.best().unwrap().confidence, 1.0);
+            assert_eq!(detection.rejected.len(), 22);
+        }
+    }
+
+    #[test]
+    pub fn test_ambiguous() {
+        // hex only, the other digit based layouts reject the letters
+        let detection = detect("D2FE28\n");
+        assert_eq!(detection.days(), vec![16]);
+
+        let detection = detect("3,4,3,1,2");
+        assert_eq!(detection.best().unwrap().day, 6);
+        assert!(detection.days().contains(&7));
+
+        let detection = detect("199\n200\n208");
+        assert_eq!(detection.days(), vec![1, 9, 15]);
+    }
+
+    #[test]
+    pub fn test_rejected() {
+        let detection = detect("target area: x=20..30, y=-10..-5");
+        let day21 = detection.rejected.iter().find(|r| r.day == 21).unwrap();
+        assert_eq!(day21.reason, "does not look like a day21 input");
+
+        // matches the layout, but the parser refuses it
+        let detection = detect(&"1".repeat(200));
+        assert!(!detection.days().contains(&3));
+        let day3 = detection.rejected.iter().find(|r| r.day == 3).unwrap();
+        assert_eq!(day3.reason, "line 1: 200 bits, at most 128 are supported");
+
+        let detection = detect("D2FE2");
+        let day16 = detection.rejected.iter().find(|r| r.day == 16).unwrap();
+        assert_eq!(day16.reason, "Odd number of digits");
+
+        let detection = detect("199\n99999999999");
+        assert_eq!(detection.days(), vec![9, 15]);
+        let day1 = detection.rejected.iter().find(|r| r.day == 1).unwrap();
+        assert_eq!(day1.reason, "number too large to fit in target type");
+    }
+
+    #[test]
+    pub fn test_crlf() {
+        assert_eq!(detect("forward 5\r\ndown 3\r\n").days(), vec![2]);
+        assert_eq!(detect("Player 1 starting position: 4\r\nPlayer 2 starting position: 8\r\n").days(), vec![21]);
+    }
+}
@@ -29,5 +29,10 @@ pub mod day21;
 pub mod day22;
 pub mod day23;
 
+pub mod count;
+pub mod detect;
+pub mod scaling;
+pub mod synth;
+
 
 aoc_lib!{ year = 2021 }
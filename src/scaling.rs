@@ -0,0 +1,130 @@
+//! Time solvers on synthetic inputs of growing size and estimate their empirical complexity.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+/// A solver run on inputs of increasing size.
+pub struct Scenario {
+    pub solver: &'static str,
+    /// what the size is counting
+    pub parameter: &'static str,
+    pub sizes: Vec<usize>,
+    // builds the input of the given size and returns the seconds per solver run
+    run: fn(usize) -> f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub solver: &'static str,
+    pub parameter: &'static str,
+    /// (size, seconds per run)
+    pub timings: Vec<(usize, f64)>,
+}
+
+impl Series {
+    /// Exponent k of the best fit `seconds = c * size^k`.
+    pub fn exponent(&self) -> f64 {
+        fit_exponent(&self.timings.iter().map(|&(n, t)| (n as f64, t)).collect::<Vec<_>>())
+    }
+}
+
+/// Least squares slope of the log-log points.
+pub fn fit_exponent(points: &[(f64, f64)]) -> f64 {
+    let logs = points.iter().map(|(x, y)| (x.ln(), y.ln())).collect::<Vec<_>>();
+    let n = logs.len() as f64;
+    let (mx, my) = logs.iter().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+    let (cov, var) = logs.iter().fold((0.0, 0.0), |(c, v), (x, y)| (c + (x - mx) * (y - my), v + (x - mx) * (x - mx)));
+    cov / var
+}
+
+/// Mean time of `f`, repeating fast runs until they add up to at least 50ms.
+pub fn time<F: FnMut()>(mut f: F) -> f64 {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed() < Duration::from_millis(50) {
+        f();
+        runs += 1;
+    }
+    start.elapsed().as_secs_f64() / runs as f64
+}
+
+pub fn scenarios() -> Vec<Scenario> {
+    vec![
+        Scenario { solver: "day1::part2", parameter: "depths", sizes: vec![1000, 2000, 4000, 8000, 16000], run: |n| {
+            let input = day1::generator(&synth::day1(n));
+            time(|| { black_box(day1::part2(&input)); })
+        }},
+        Scenario { solver: "day5::part1", parameter: "vent lines", sizes: vec![125, 250, 500, 1000, 2000], run: |n| {
+            let input = day5::generator(&synth::day5(n, 1000));
+            time(|| { black_box(day5::part1(&input)); })
+        }},
+        Scenario { solver: "day5::part2", parameter: "vent lines", sizes: vec![125, 250, 500, 1000, 2000], run: |n| {
+            let input = day5::generator(&synth::day5(n, 1000));
+            time(|| { black_box(day5::part2(&input)); })
+        }},
+        Scenario { solver: "day6::part2_rotate", parameter: "days", sizes: vec![50, 100, 200, 400], run: |n| {
+            let input = day6::generator(&synth::day6(300));
+            time(|| { black_box(day6::run(&input, n as u32)); })
+        }},
+        Scenario { solver: "day14::step_n", parameter: "polymer steps", sizes: vec![5, 10, 20, 40], run: |n| {
+            let (base, rule, num) = day14::generator(&synth::day14(20));
+            time(|| { black_box(day14::step_n(base.clone(), num.clone(), rule.clone(), n as i32)); })
+        }},
+        Scenario { solver: "day15::part1", parameter: "grid edge", sizes: vec![25, 50, 100, 200], run: |n| {
+            let input = day15::generator(&synth::digit_grid(n, 1, 9));
+            time(|| { black_box(day15::part1(&input)); })
+        }},
+        Scenario { solver: "day18::part2", parameter: "numbers", sizes: vec![10, 20, 40, 80], run: |n| {
+            let input = day18::generator(&synth::day18(n));
+            time(|| { black_box(day18::part2(&input)); })
+        }},
+        // part1 only looks at the first 20 cuboids
+        Scenario { solver: "day22::part1", parameter: "cuboids", sizes: vec![3, 5, 10, 20], run: |n| {
+            let input = day22::generator(&synth::day22(n, 20));
+            time(|| { black_box(day22::part1(&input)); })
+        }},
+    ]
+}
+
+impl Scenario {
+    pub fn measure(&self) -> Series {
+        Series {
+            solver: self.solver,
+            parameter: self.parameter,
+            timings: self.sizes.iter().map(|&n| (n, (self.run)(n))).collect(),
+        }
+    }
+}
+
+pub fn to_csv(series: &[Series]) -> String {
+    let mut csv = "solver,parameter,size,seconds,exponent\n".to_string();
+    for s in series {
+        let exponent = s.exponent();
+        for (n, t) in &s.timings {
+            csv += &format!("{},{},{},{:.3e},{:.2}\n", s.solver, s.parameter, n, t, exponent);
+        }
+    }
+    csv
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_fit() {
+        let square = (1..10).map(|n| (n as f64, 3.0 * (n * n) as f64)).collect::<Vec<_>>();
+        assert!((fit_exponent(&square) - 2.0).abs() < 1e-9);
+        let linear = (1..10).map(|n| (n as f64, 0.5 * n as f64)).collect::<Vec<_>>();
+        assert!((fit_exponent(&linear) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    pub fn test_csv() {
+        let series = Series { solver: "day1::part1", parameter: "depths", timings: vec![(10, 1.0), (100, 10.0)] };
+        assert_eq!(to_csv(&[series]), "solver,parameter,size,seconds,exponent\nday1::part1,depths,10,1.000e0,1.00\nday1::part1,depths,100,1.000e1,1.00\n");
+    }
+}
@@ -0,0 +1,263 @@
+//! Deterministic synthetic puzzle inputs of configurable size, for benchmarks and scaling runs.
+
+use itertools::Itertools;
+use crate::day16::{self, Packet};
+
+/// xorshift64, good enough for generating inputs and reproducible without extra dependencies.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in `lo..=hi`.
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo + 1) as u64) as i64
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.range(0, items.len() as i64 - 1) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0, i as i64) as usize);
+        }
+    }
+}
+
+const SEED: u64 = 2021;
+
+pub fn day1(depths: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    let mut depth = 100;
+    (0..depths).map(|_| {
+        depth += rng.range(-10, 20);
+        depth.to_string()
+    }).join("\n")
+}
+
+pub fn day2(commands: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    (0..commands).map(|_| format!("{} {}", rng.pick(&["forward", "down", "up"]), rng.range(1, 9))).join("\n")
+}
+
+/// Up to 4096 distinct 12 bit readings with unique oxygen and CO2 ratings.
+pub fn day3(lines: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    let mut readings = (0..4096).collect::<Vec<u64>>();
+    rng.shuffle(&mut readings);
+    readings.truncate(lines);
+    // the least common walk runs dry where all candidates share a bit, so flip it in one of them. The
+    // candidates are all readings with the prefix walked so far, so nothing else has the flipped prefix.
+    let mut candidates = (0..readings.len()).collect::<Vec<_>>();
+    for bit in (0..12).rev() {
+        if candidates.len() < 2 {
+            break;
+        }
+        let ones = |readings: &[u64], candidates: &[usize]| candidates.iter().filter(|&&i| readings[i] >> bit & 1 == 1).count();
+        if ones(&readings, &candidates) % candidates.len() == 0 {
+            readings[candidates[0]] ^= 1 << bit;
+        }
+        let keep = (2 * ones(&readings, &candidates) < candidates.len()) as u64;
+        candidates.retain(|&i| readings[i] >> bit & 1 == keep);
+    }
+    readings.iter().map(|r| format!("{:012b}", r)).join("\n")
+}
+
+pub fn day4(boards: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    let mut numbers = (0..100).collect::<Vec<_>>();
+    rng.shuffle(&mut numbers);
+    let boards = (0..boards).map(|_| {
+        rng.shuffle(&mut numbers);
+        numbers[..25].chunks(5).map(|row| row.iter().map(|n| format!("{:2}", n)).join(" ")).join("\n")
+    }).collect::<Vec<_>>();
+    rng.shuffle(&mut numbers);
+    format!("{}\n\n{}", numbers.iter().join(","), boards.join("\n\n"))
+}
+
+/// Horizontal, vertical and diagonal vent lines inside a `size` x `size` area.
+pub fn day5(lines: usize, size: i64) -> String {
+    let mut rng = Rng::new(SEED);
+    // room left from p when walking in direction d
+    let room = |p: i64, d: i64| match d {
+        1 => size - 1 - p,
+        -1 => p,
+        _ => size,
+    };
+    (0..lines).map(|_| {
+        let (x1, y1) = (rng.range(0, size - 1), rng.range(0, size - 1));
+        let (dx, dy) = rng.pick(&[(1, 0), (0, 1), (1, 1), (1, -1)]);
+        let len = rng.range(0, room(x1, dx).min(room(y1, dy)));
+        format!("{},{} -> {},{}", x1, y1, x1 + dx * len, y1 + dy * len)
+    }).join("\n")
+}
+
+pub fn day6(fishes: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    (0..fishes).map(|_| rng.range(1, 5)).join(",")
+}
+
+pub fn day7(crabs: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    (0..crabs).map(|_| rng.range(0, 2000)).join(",")
+}
+
+pub fn day8(lines: usize) -> String {
+    const DIGITS: [&str; 10] = ["abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg"];
+    fn scramble(rng: &mut Rng, wires: &[char], digit: &str) -> String {
+        let mut s = digit.chars().map(|c| wires[(c as u8 - b'a') as usize]).collect::<Vec<_>>();
+        rng.shuffle(&mut s);
+        s.into_iter().collect()
+    }
+    let mut rng = Rng::new(SEED);
+    (0..lines).map(|_| {
+        let mut wires = "abcdefg".chars().collect::<Vec<_>>();
+        rng.shuffle(&mut wires);
+        let mut patterns = DIGITS.iter().map(|d| scramble(&mut rng, &wires, d)).collect::<Vec<_>>();
+        let output = (0..4).map(|_| {
+            let digit = rng.pick(&DIGITS);
+            scramble(&mut rng, &wires, digit)
+        }).collect::<Vec<_>>();
+        rng.shuffle(&mut patterns);
+        format!("{} | {}", patterns.join(" "), output.join(" "))
+    }).join("\n")
+}
+
+/// Square grid of single digits, as used by day9, day11 and day15.
+pub fn digit_grid(edge: usize, lo: i64, hi: i64) -> String {
+    let mut rng = Rng::new(SEED);
+    (0..edge).map(|_| (0..edge).map(|_| rng.range(lo, hi)).join("")).join("\n")
+}
+
+pub fn day10(lines: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    (0..lines).map(|_| {
+        let mut stack = Vec::new();
+        let mut line = String::new();
+        for _ in 0..rng.range(10, 100) {
+            if stack.is_empty() || rng.range(0, 1) == 0 {
+                let (open, close) = rng.pick(&[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')]);
+                line.push(open);
+                stack.push(close);
+            } else {
+                line.push(stack.pop().unwrap());
+            }
+        }
+        // about half of the lines get corrupted, the rest stay incomplete
+        if rng.range(0, 1) == 0 {
+            line.push(rng.pick(&[')', ']', '}', '>']));
+        }
+        line
+    }).join("\n")
+}
+
+/// `points` dots on a `2^folds + 1` wide sheet, folded in half alternately along x and y.
+pub fn day13(points: usize, folds: u32) -> String {
+    let mut rng = Rng::new(SEED);
+    let size = 1i64 << folds;
+    let dots = (0..points).map(|_| format!("{},{}", rng.range(0, size), rng.range(0, size))).join("\n");
+    let instructions = (0..folds).map(|f| format!("fold along {}={}", if f % 2 == 0 { 'x' } else { 'y' }, (size >> (f / 2 + 1)))).join("\n");
+    format!("{}\n\n{}", dots, instructions)
+}
+
+/// Template of the given length with a complete rule set over ten elements.
+pub fn day14(template: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    let elements = "BCFHKNOPSV".chars().collect::<Vec<_>>();
+    let base = (0..template).map(|_| rng.pick(&elements)).collect::<String>();
+    let rules = elements.iter().cartesian_product(elements.iter())
+        .map(|(a, b)| format!("{}{} -> {}", a, b, rng.pick(&elements)))
+        .join("\n");
+    format!("{}\n\n{}", base, rules)
+}
+
+/// Hex transmission of a random packet tree of the given depth.
+pub fn day16(depth: u32) -> String {
+    fn packet(rng: &mut Rng, depth: u32) -> Packet {
+        let v = rng.range(0, 7) as u64;
+        if depth == 0 {
+            return Packet::literal(v, rng.range(0, 1 << 20) as u64);
+        }
+        // no products, they overflow quickly on deep trees
+        let t = rng.pick(&[0, 2, 3, 5, 6, 7]);
+        let n = if t >= 5 { 2 } else { rng.range(1, 4) };
+        let sub = (0..n).map(|_| packet(rng, depth - 1)).collect();
+        Packet::operator(v, t, sub, rng.range(0, 1) == 0)
+    }
+    day16::to_hex(&packet(&mut Rng::new(SEED), depth).encode())
+}
+
+pub fn day17(scale: i64) -> String {
+    format!("target area: x={}..{}, y={}..{}", 10 * scale, 15 * scale, -5 * scale, -2 * scale)
+}
+
+pub fn day18(numbers: usize) -> String {
+    fn element(rng: &mut Rng, depth: u32) -> String {
+        if depth == 4 || (depth > 0 && rng.range(0, 2) == 0) {
+            rng.range(0, 9).to_string()
+        } else {
+            format!("[{},{}]", element(rng, depth + 1), element(rng, depth + 1))
+        }
+    }
+    let mut rng = Rng::new(SEED);
+    (0..numbers).map(|_| element(&mut rng, 0)).join("\n")
+}
+
+pub fn day20(edge: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    let algorithm = (0..512).map(|i| if i > 0 && rng.range(0, 1) == 0 { '#' } else { '.' }).collect::<String>();
+    let image = (0..edge).map(|_| (0..edge).map(|_| rng.pick(&['#', '.'])).collect::<String>()).join("\n");
+    format!("{}\n\n{}", algorithm, image)
+}
+
+/// Cuboids with sides up to `side` within -50..=50, the region part1 considers.
+pub fn day22(cuboids: usize, side: i64) -> String {
+    let mut rng = Rng::new(SEED);
+    (0..cuboids).map(|_| {
+        let range = |rng: &mut Rng| {
+            let lo = rng.range(-50, 50 - side);
+            format!("{}..{}", lo, lo + rng.range(0, side))
+        };
+        let state = if rng.range(0, 3) == 0 { "off" } else { "on" };
+        format!("{} x={},y={},z={}", state, range(&mut rng), range(&mut rng), range(&mut rng))
+    }).join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detect::detect;
+
+    #[test]
+    pub fn test_detected() {
+        let inputs = [
+            (day2(50), 2), (day4(5), 4), (day5(50, 100), 5), (day8(10), 8),
+            (day13(50, 6), 13), (day14(20), 14), (day17(3), 17), (day22(10, 20), 22),
+        ];
+        for (input, day) in inputs {
+            assert_eq!(detect(&input).days(), vec![day], "{}", input);
+        }
+        assert!(detect(&day16(3)).days().contains(&16));
+        assert!(detect(&day1(50)).days().contains(&1));
+        assert!(detect(&day10(50)).days().contains(&10));
+    }
+
+    #[test]
+    pub fn test_day3_distinct() {
+        for lines in [10, 1000, 4096] {
+            let input = day3(lines);
+            assert_eq!(input.lines().unique().count(), lines);
+        }
+    }
+}